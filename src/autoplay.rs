@@ -0,0 +1,194 @@
+//! Plays games without a terminal, to measure how often boards can be won
+//! and how much guessing they take.
+
+use crate::difficulty::Difficulty;
+use crate::engine::{Engine, GameOptions, GameState};
+use crate::mineboard::BoardError;
+use crate::solver::Solver;
+use crate::utils::{Coordinates, Seed};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How an automatic game went.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub won: bool,
+    /// Cells opened without being sure they were safe.
+    pub guesses: usize,
+}
+
+/// Plays `engine` to the end through the game rules. The first click is in
+/// the middle of the board. After that every cell the numbers prove is
+/// flagged or opened, and only when there is none is the closed cell least
+/// likely to hold a landmine opened.
+pub fn play(engine: &mut Engine) -> Result<Report, BoardError> {
+    let config = engine.board().get_config();
+    let middle = Coordinates::new(config.width / 2, config.height / 2);
+    engine.reveal(&middle)?;
+
+    let mut report = Report::default();
+    while !engine.is_over() {
+        let solver = Solver::new(engine.board())?;
+        let deduction = solver.deduce();
+        if deduction.safe.is_empty() {
+            let (pos, chance) = safest_cell(engine, &solver)?;
+            if chance > 0.0 {
+                report.guesses += 1;
+            }
+            engine.reveal(&pos)?;
+            continue;
+        }
+        for pos in &deduction.landmines {
+            if !engine.board().have_flag(pos)? {
+                engine.toggle_flag(pos)?;
+            }
+        }
+        for pos in &deduction.safe {
+            engine.reveal(pos)?;
+        }
+    }
+    report.won = engine.state() == &GameState::Win;
+    Ok(report)
+}
+
+/// The closed, unflagged cell least likely to hold a landmine, with that
+/// chance.
+fn safest_cell(engine: &Engine, solver: &Solver) -> Result<(Coordinates, f64), BoardError> {
+    let board = engine.board();
+    let config = board.get_config();
+    // Only proven landmines are flagged, so some layout always fits, but
+    // there can be too many of them to count.
+    let chances = solver
+        .probabilities(*engine.landmine_count())
+        .unwrap_or_else(|_| vec![vec![0.5; config.width]; config.height]);
+    let mut best = None;
+    for (y, row) in chances.iter().enumerate() {
+        for (x, chance) in row.iter().enumerate() {
+            let pos = Coordinates::new(x, y);
+            if board.is_opened(&pos)? || board.have_flag(&pos)? {
+                continue;
+            }
+            if best.is_none_or(|(_, best)| *chance < best) {
+                best = Some((pos, *chance));
+            }
+        }
+    }
+    // A game that is not over always has a closed safe cell.
+    Ok(best.expect("no closed cell left"))
+}
+
+/// Results of playing many seeded games on one difficulty.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Benchmark {
+    pub difficulty: Difficulty,
+    pub games: usize,
+    pub won: usize,
+    pub guesses: usize,
+    pub time: Duration,
+}
+
+impl Benchmark {
+    /// Plays `games` games of `difficulty` with `options`. Game `i` uses
+    /// the seed `options.seed + i`, counting from 0 without a seed, so a
+    /// run can be repeated.
+    pub fn run(
+        difficulty: &Difficulty,
+        games: usize,
+        options: &GameOptions,
+    ) -> Result<Self, BoardError> {
+        let first_seed = options.seed.map_or(0, |seed| seed.0);
+        let mut benchmark = Benchmark {
+            difficulty: difficulty.clone(),
+            games,
+            won: 0,
+            guesses: 0,
+            time: Duration::ZERO,
+        };
+        for i in 0..games {
+            let options = GameOptions {
+                seed: Some(Seed(first_seed.wrapping_add(i as u64))),
+                ..options.clone()
+            };
+            let start = Instant::now();
+            let mut engine =
+                Engine::new(difficulty.board_config(), difficulty.landmines(), options)?;
+            let report = play(&mut engine)?;
+            benchmark.time += start.elapsed();
+            benchmark.won += report.won as usize;
+            benchmark.guesses += report.guesses;
+        }
+        Ok(benchmark)
+    }
+
+    /// Share of games won, from 0 to 1.
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.games > 0).then(|| self.won as f64 / self.games as f64)
+    }
+
+    pub fn guesses_per_game(&self) -> Option<f64> {
+        (self.games > 0).then(|| self.guesses as f64 / self.games as f64)
+    }
+
+    pub fn time_per_game(&self) -> Option<Duration> {
+        (self.games > 0).then(|| self.time / self.games as u32)
+    }
+}
+
+impl fmt::Display for Benchmark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<12} {:>6} games  won {:>5.1}%  {:>5.2} guesses/game  {:>8.3}ms/game",
+            self.difficulty.name(),
+            self.games,
+            self.win_rate().unwrap_or(0.0) * 100.0,
+            self.guesses_per_game().unwrap_or(0.0),
+            self.time_per_game().unwrap_or_default().as_secs_f64() * 1000.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::FirstClick;
+    use crate::generator::Generator;
+
+    #[test]
+    fn no_guess_boards_are_won_without_guessing() {
+        for seed in 0..10 {
+            let difficulty = Difficulty::Beginner;
+            let options = GameOptions {
+                seed: Some(Seed(seed)),
+                first_click: FirstClick::Opening,
+                generator: Generator::NoGuess { attempts: 1000 },
+                ..GameOptions::default()
+            };
+            let mut engine =
+                Engine::new(difficulty.board_config(), difficulty.landmines(), options).unwrap();
+            let report = play(&mut engine).unwrap();
+            assert_eq!(
+                report,
+                Report {
+                    won: true,
+                    guesses: 0
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn benchmark_counts_every_game() {
+        let options = GameOptions {
+            seed: Some(Seed(100)),
+            ..GameOptions::default()
+        };
+        let first = Benchmark::run(&Difficulty::Beginner, 20, &options).unwrap();
+        assert_eq!(first.games, 20);
+        assert!(first.won > 0 && first.won <= 20);
+
+        // The same seeds play the same games.
+        let second = Benchmark::run(&Difficulty::Beginner, 20, &options).unwrap();
+        assert_eq!((second.won, second.guesses), (first.won, first.guesses));
+    }
+}
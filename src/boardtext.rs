@@ -0,0 +1,219 @@
+//! Plain-text board layouts, one line per row:
+//!
+//! | Cell | Meaning                   |
+//! |------|---------------------------|
+//! | `.`  | closed safe cell          |
+//! | `*`  | closed landmine           |
+//! | `o`  | opened safe cell          |
+//! | `F`  | flagged landmine          |
+//! | `f`  | flagged safe cell         |
+//! | `!`  | question-marked landmine  |
+//! | `?`  | question-marked safe cell |
+//! | `X`  | opened landmine           |
+//!
+//! Lines starting with `#` and blank lines are ignored.
+
+use crate::mineboard::{Board, BoardConfig, BoardError, ItemType, LandmineField, Mark};
+use crate::utils::Coordinates;
+use crossterm::style::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cell {
+    landmine: bool,
+    opened: bool,
+    mark: Mark,
+}
+
+struct Layout {
+    width: usize,
+    height: usize,
+    cells: Vec<(Coordinates, Cell)>,
+}
+
+fn parse_cell(c: char) -> Option<Cell> {
+    let (landmine, opened, mark) = match c {
+        '.' => (false, false, Mark::None),
+        '*' => (true, false, Mark::None),
+        'o' => (false, true, Mark::None),
+        'F' => (true, false, Mark::Flag),
+        'f' => (false, false, Mark::Flag),
+        '!' => (true, false, Mark::Question),
+        '?' => (false, false, Mark::Question),
+        'X' => (true, true, Mark::None),
+        _ => return None,
+    };
+    Some(Cell {
+        landmine,
+        opened,
+        mark,
+    })
+}
+
+fn parse_layout(text: &str) -> Result<Layout, BoardError> {
+    let mut width = None;
+    let mut cells = vec![];
+    let mut y = 0;
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut x = 0;
+        for (column, c) in line.chars().enumerate() {
+            let cell = parse_cell(c).ok_or(BoardError::InvalidCell {
+                line: line_number,
+                column: column + 1,
+                found: c,
+            })?;
+            cells.push((Coordinates::new(x, y), cell));
+            x += 1;
+        }
+
+        match width {
+            None => width = Some(x),
+            Some(expected) if expected != x => {
+                return Err(BoardError::UnevenRow {
+                    line: line_number,
+                    expected,
+                    found: x,
+                })
+            }
+            _ => (),
+        }
+        y += 1;
+    }
+
+    match width {
+        Some(width) => Ok(Layout {
+            width,
+            height: y,
+            cells,
+        }),
+        None => Err(BoardError::EmptyBoard),
+    }
+}
+
+/// Reads only the landmines of a text layout.
+pub fn parse_landmines(text: &str) -> Result<LandmineField, BoardError> {
+    let layout = parse_layout(text)?;
+    let landmines: Vec<Coordinates> = layout
+        .cells
+        .iter()
+        .filter(|(_, cell)| cell.landmine)
+        .map(|(pos, _)| *pos)
+        .collect();
+    LandmineField::new(&landmines, layout.width, layout.height)
+}
+
+/// Reads a text layout into a board, including opened cells and marks. An
+/// opened landmine lost the game, so the landmines are shown with it
+/// exploded.
+pub fn parse_board(text: &str) -> Result<Board, BoardError> {
+    let layout = parse_layout(text)?;
+    let landmines: Vec<Coordinates> = layout
+        .cells
+        .iter()
+        .filter(|(_, cell)| cell.landmine)
+        .map(|(pos, _)| *pos)
+        .collect();
+    let config = BoardConfig::unicode(layout.width, layout.height, Color::Reset);
+    let mut board = Board::new(config, &landmines)?;
+    for (pos, cell) in &layout.cells {
+        if cell.opened {
+            board.open_cell(pos)?;
+            if cell.landmine {
+                board.reveal_landmines(Some(*pos));
+            }
+        }
+        board.set_mark(pos, cell.mark)?;
+    }
+    Ok(board)
+}
+
+/// Writes the landmines, opened cells and marks of `board` as text.
+pub fn board_to_text(board: &Board) -> Result<String, BoardError> {
+    let config = board.get_config();
+    let mut text = String::new();
+    for y in 0..config.height {
+        for x in 0..config.width {
+            let pos = Coordinates::new(x, y);
+            let landmine = board.get(&pos)? == ItemType::Landmine;
+            let c = match (board.mark(&pos)?, landmine) {
+                (Mark::Flag, true) => 'F',
+                (Mark::Flag, false) => 'f',
+                (Mark::Question, true) => '!',
+                (Mark::Question, false) => '?',
+                (Mark::None, true) if board.is_opened(&pos)? => 'X',
+                (Mark::None, true) => '*',
+                (Mark::None, false) if board.is_opened(&pos)? => 'o',
+                (Mark::None, false) => '.',
+            };
+            text.push(c);
+        }
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mineboard::Field;
+
+    #[test]
+    fn round_trip() {
+        let text = "..*.\noooF\nf.?!\n";
+        let board = parse_board(text).unwrap();
+
+        assert_eq!(board.get_config().width, 4);
+        assert_eq!(board.get_config().height, 3);
+        assert_eq!(*board.landmine_count(), 3);
+        assert!(board.is_opened(&Coordinates::new(1, 1)).unwrap());
+        assert!(board.have_flag(&Coordinates::new(0, 2)).unwrap());
+        assert_eq!(board.mark(&Coordinates::new(2, 2)).unwrap(), Mark::Question);
+        assert_eq!(board_to_text(&board).unwrap(), text);
+    }
+
+    #[test]
+    fn exploded_landmine_round_trip() {
+        let text = "oX*\nooo\n";
+        let board = parse_board(text).unwrap();
+        assert!(board.is_opened(&Coordinates::new(1, 0)).unwrap());
+        assert!(!board.is_cleared());
+        assert_eq!(board_to_text(&board).unwrap(), text);
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        let landmines = parse_landmines("# a board\n\n*.\n.*\n\n").unwrap();
+        assert_eq!(*landmines.get_count(), 2);
+        assert_eq!(*landmines.get_width(), 2);
+        assert_eq!(*landmines.get_height(), 2);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse_landmines("# nothing\n").err(),
+            Some(BoardError::EmptyBoard)
+        );
+        assert_eq!(
+            parse_landmines("...\n..\n").err(),
+            Some(BoardError::UnevenRow {
+                line: 2,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            parse_landmines("...\n.x.\n").err(),
+            Some(BoardError::InvalidCell {
+                line: 2,
+                column: 2,
+                found: 'x'
+            })
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use crate::mineboard::{BoardConfig, BoardError};
+use crate::utils::ParseOptionError;
+use crossterm::style::Color;
+use std::fmt;
+use std::str::FromStr;
+
+/// Board size and landmine count of a game.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Difficulty {
+    #[default]
+    Beginner,
+    Intermediate,
+    Expert,
+    Custom {
+        width: usize,
+        height: usize,
+        landmines: usize,
+    },
+}
+
+/// Lines the game prints below the status line, for messages and the
+/// result.
+const LINES_BELOW_BOARD: usize = 2;
+
+impl Difficulty {
+    pub const PRESETS: [Difficulty; 3] = [
+        Difficulty::Beginner,
+        Difficulty::Intermediate,
+        Difficulty::Expert,
+    ];
+
+    /// A custom board, checked so that it can hold its landmines and still
+    /// leave a cell free for the first click.
+    pub fn custom(width: usize, height: usize, landmines: usize) -> Result<Self, BoardError> {
+        if width == 0 || height == 0 {
+            return Err(BoardError::EmptyBoard);
+        }
+        if landmines >= width * height {
+            return Err(BoardError::TooManyLandmines);
+        }
+        Ok(Difficulty::Custom {
+            width,
+            height,
+            landmines,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "Beginner",
+            Difficulty::Intermediate => "Intermediate",
+            Difficulty::Expert => "Expert",
+            Difficulty::Custom { .. } => "Custom",
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            Difficulty::Beginner => 9,
+            Difficulty::Intermediate => 16,
+            Difficulty::Expert => 30,
+            Difficulty::Custom { width, .. } => *width,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self {
+            Difficulty::Beginner => 9,
+            Difficulty::Intermediate => 16,
+            Difficulty::Expert => 16,
+            Difficulty::Custom { height, .. } => *height,
+        }
+    }
+
+    pub fn landmines(&self) -> usize {
+        match self {
+            Difficulty::Beginner => 10,
+            Difficulty::Intermediate => 40,
+            Difficulty::Expert => 99,
+            Difficulty::Custom { landmines, .. } => *landmines,
+        }
+    }
+
+    pub fn board_config(&self) -> BoardConfig {
+        BoardConfig::unicode(self.width(), self.height(), Color::Reset)
+    }
+
+    /// Short form used in the settings file: the lowercase preset name, or
+    /// `custom:<width>x<height>:<landmines>`.
+    pub fn key(&self) -> String {
+        match self {
+            Difficulty::Custom {
+                width,
+                height,
+                landmines,
+            } => format!("custom:{}x{}:{}", width, height, landmines),
+            _ => self.name().to_lowercase(),
+        }
+    }
+
+    /// Columns and rows of terminal needed to play.
+    pub fn terminal_size(&self) -> (usize, usize) {
+        let (columns, rows) = self.board_config().screen_size();
+        (columns, rows + LINES_BELOW_BOARD)
+    }
+
+    pub fn fits(&self, columns: usize, rows: usize) -> bool {
+        let (needed_columns, needed_rows) = self.terminal_size();
+        needed_columns <= columns && needed_rows <= rows
+    }
+
+    /// Largest width and height of a board that fits in a terminal of
+    /// `columns` by `rows`.
+    pub fn max_size(columns: usize, rows: usize) -> (usize, usize) {
+        let (border_columns, border_rows) = Difficulty::Custom {
+            width: 0,
+            height: 0,
+            landmines: 0,
+        }
+        .terminal_size();
+        let char_width = BoardConfig::unicode(1, 1, Color::Reset).char_width as usize;
+        (
+            columns.saturating_sub(border_columns) / char_width,
+            rows.saturating_sub(border_rows),
+        )
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}x{}, {} landmines)",
+            self.name(),
+            self.width(),
+            self.height(),
+            self.landmines()
+        )
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = ParseOptionError;
+
+    /// Reads the form written by [`Difficulty::key`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(preset) = Difficulty::PRESETS
+            .iter()
+            .find(|difficulty| difficulty.key() == s)
+        {
+            return Ok(preset.clone());
+        }
+        let error = || ParseOptionError(s.to_string());
+        let custom = s.strip_prefix("custom:").ok_or_else(error)?;
+        let (size, landmines) = custom.split_once(':').ok_or_else(error)?;
+        let (width, height) = size.split_once('x').ok_or_else(error)?;
+        Difficulty::custom(
+            width.parse().map_err(|_| error())?,
+            height.parse().map_err(|_| error())?,
+            landmines.parse().map_err(|_| error())?,
+        )
+        .map_err(|_| error())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn presets() {
+        let expert = Difficulty::Expert.board_config();
+        assert_eq!((expert.width, expert.height), (30, 16));
+        assert_eq!(Difficulty::Expert.landmines(), 99);
+        assert_eq!(
+            Difficulty::Beginner.to_string(),
+            "Beginner (9x9, 10 landmines)"
+        );
+    }
+
+    #[test]
+    fn custom_is_validated() {
+        assert_eq!(Difficulty::custom(0, 5, 1), Err(BoardError::EmptyBoard));
+        assert_eq!(
+            Difficulty::custom(3, 3, 9),
+            Err(BoardError::TooManyLandmines)
+        );
+        assert_eq!(Difficulty::custom(3, 3, 8).unwrap().landmines(), 8);
+    }
+
+    #[test]
+    fn key_round_trip() {
+        let custom = Difficulty::custom(20, 10, 30).unwrap();
+        assert_eq!(custom.key(), "custom:20x10:30");
+        assert_eq!("custom:20x10:30".parse::<Difficulty>(), Ok(custom));
+        assert_eq!("expert".parse::<Difficulty>(), Ok(Difficulty::Expert));
+        assert!("custom:3x3:9".parse::<Difficulty>().is_err());
+        assert!("hard".parse::<Difficulty>().is_err());
+    }
+
+    #[test]
+    fn terminal_bounds() {
+        assert_eq!(Difficulty::Expert.terminal_size(), (64, 21));
+        assert!(Difficulty::Expert.fits(80, 24));
+        assert!(!Difficulty::Expert.fits(63, 24));
+
+        let (width, height) = Difficulty::max_size(80, 24);
+        assert_eq!((width, height), (38, 19));
+        assert!(Difficulty::custom(width, height, 1).unwrap().fits(80, 24));
+        assert!(!Difficulty::custom(width + 1, height, 1)
+            .unwrap()
+            .fits(80, 24));
+        assert_eq!(Difficulty::max_size(2, 2), (0, 0));
+    }
+}
@@ -0,0 +1,844 @@
+use crate::generator::Generator;
+use crate::mineboard::{Board, BoardConfig, BoardError, ClickResult, ItemType, Mark};
+use crate::solver::{Hint, ProbabilityError, Solver};
+use crate::utils::{Coordinates, ParseOptionError, Seed};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameState {
+    Playing,
+    Win,
+    HitMine,
+    Quit,
+}
+
+/// Which cells are guaranteed to be free of landmines on the first click.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum FirstClick {
+    /// Only the clicked cell is safe.
+    #[default]
+    Safe,
+    /// The clicked cell and its neighbours are safe, so the first click
+    /// always opens a region.
+    Opening,
+}
+
+impl fmt::Display for FirstClick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FirstClick::Safe => write!(f, "safe"),
+            FirstClick::Opening => write!(f, "opening"),
+        }
+    }
+}
+
+impl FromStr for FirstClick {
+    type Err = ParseOptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "safe" => Ok(FirstClick::Safe),
+            "opening" => Ok(FirstClick::Opening),
+            _ => Err(ParseOptionError(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GameOptions {
+    /// Also win once every landmine is flagged, without opening every cell.
+    pub flag_win: bool,
+    pub first_click: FirstClick,
+    pub generator: Generator,
+    /// Seed for the landmine layout. A random one is picked when `None`.
+    /// The same seed gives the same board wherever the first click is,
+    /// except that landmines under the safe cells are moved. A no-guess
+    /// board also depends on the first click.
+    pub seed: Option<Seed>,
+    /// Keep a history so moves can be undone, even a losing click.
+    pub practice: bool,
+    /// Marking a flagged cell turns the flag into a question mark instead
+    /// of clearing it.
+    pub question_marks: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Reveal(Coordinates),
+    Flag(Coordinates),
+    Chord(Coordinates),
+}
+
+/// Something that happened on the board because of an action.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// The requested generator failed and a random layout was used instead.
+    GenerationFailed(BoardError),
+    Opened(Vec<Coordinates>),
+    Flagged(Coordinates),
+    Unflagged(Coordinates),
+    Questioned(Coordinates),
+    Unquestioned(Coordinates),
+    HitLandmine(Coordinates),
+    Won,
+}
+
+/// Clicks made during a game.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Clicks {
+    pub left: usize,
+    pub right: usize,
+    pub chord: usize,
+    /// Clicks that opened a cell or flagged a landmine.
+    pub effective: usize,
+}
+
+impl Clicks {
+    pub fn total(&self) -> usize {
+        self.left + self.right + self.chord
+    }
+
+    /// Share of the clicks that were effective, from 0 to 1.
+    pub fn correctness(&self) -> Option<f64> {
+        (self.total() > 0).then(|| self.effective as f64 / self.total() as f64)
+    }
+}
+
+/// How efficiently a board was cleared, as competitive players measure it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Efficiency {
+    /// The fewest clicks the board can be cleared in, see
+    /// [`Board::three_bv`].
+    pub three_bv: usize,
+    pub clicks: Clicks,
+    pub time: Duration,
+}
+
+impl Efficiency {
+    pub fn three_bv_per_second(&self) -> Option<f64> {
+        (!self.time.is_zero()).then(|| self.three_bv as f64 / self.time.as_secs_f64())
+    }
+
+    /// Index of efficiency: 3BV per click, 1 for a perfect game.
+    pub fn ioe(&self) -> Option<f64> {
+        (self.clicks.total() > 0).then(|| self.three_bv as f64 / self.clicks.total() as f64)
+    }
+}
+
+/// A move kept in the practice history, with what it changed on the board.
+#[derive(Clone, Debug)]
+struct Step {
+    action: Action,
+    /// The mark on the cell before a flag action.
+    mark: Mark,
+    opened: Vec<Coordinates>,
+    /// Flags placed on the remaining landmines by a winning move.
+    auto_flags: Vec<Coordinates>,
+    /// Question marks cleared from the cells the move opened.
+    questions: Vec<Coordinates>,
+    /// True when the move ended the game.
+    finished: bool,
+}
+
+/// The rules of a game, without any terminal input or output.
+#[derive(Clone, Debug)]
+pub struct Engine {
+    board: Board,
+    state: GameState,
+    options: GameOptions,
+    seed: Seed,
+    landmine_count: usize,
+    landmines_placed: bool,
+    start_time: Option<Instant>,
+    end_time: Option<Instant>,
+    /// Moves that can be undone, latest last. Only kept in practice mode.
+    history: Vec<Step>,
+    /// Undone moves that can be redone, latest last.
+    undone: Vec<Action>,
+    auto_flags: Vec<Coordinates>,
+    cleared_questions: Vec<Coordinates>,
+    hints_used: usize,
+    clicks: Clicks,
+}
+
+impl Engine {
+    pub fn new(
+        config: BoardConfig,
+        landmine_count: usize,
+        options: GameOptions,
+    ) -> Result<Self, BoardError> {
+        if landmine_count >= config.width * config.height {
+            return Err(BoardError::TooManyLandmines);
+        }
+        // Landmines are placed on the first click so it can be kept safe.
+        let board = Board::new(config, &[])?;
+        let seed = options.seed.unwrap_or_else(Seed::random);
+        Ok(Self {
+            board,
+            state: GameState::Playing,
+            options,
+            seed,
+            landmine_count,
+            landmines_placed: false,
+            start_time: None,
+            end_time: None,
+            history: vec![],
+            undone: vec![],
+            auto_flags: vec![],
+            cleared_questions: vec![],
+            hints_used: 0,
+            clicks: Clicks::default(),
+        })
+    }
+
+    /// Continues a game from `board`, which already holds its landmines
+    /// when `landmines_placed` is set. `options.seed` must be set.
+    pub fn restore(
+        board: Board,
+        landmine_count: usize,
+        options: GameOptions,
+        landmines_placed: bool,
+        elapsed: Duration,
+        hints_used: usize,
+        clicks: Clicks,
+    ) -> Self {
+        let seed = options.seed.unwrap_or_else(Seed::random);
+        let start_time = if landmines_placed {
+            Instant::now().checked_sub(elapsed)
+        } else {
+            None
+        };
+        Self {
+            board,
+            state: GameState::Playing,
+            options,
+            seed,
+            landmine_count,
+            landmines_placed,
+            start_time,
+            end_time: None,
+            history: vec![],
+            undone: vec![],
+            auto_flags: vec![],
+            cleared_questions: vec![],
+            hints_used,
+            clicks,
+        }
+    }
+
+    pub fn landmines_placed(&self) -> bool {
+        self.landmines_placed
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Mutable access for frontends that draw the board.
+    pub fn board_mut(&mut self) -> &mut Board {
+        &mut self.board
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.state != GameState::Playing
+    }
+
+    pub fn seed(&self) -> &Seed {
+        &self.seed
+    }
+
+    pub fn options(&self) -> &GameOptions {
+        &self.options
+    }
+
+    pub fn landmine_count(&self) -> &usize {
+        &self.landmine_count
+    }
+
+    /// Landmines not yet accounted for by a flag. Negative when there are
+    /// more flags than landmines.
+    pub fn remaining_landmines(&self) -> i64 {
+        self.landmine_count as i64 - *self.board.flag_count() as i64
+    }
+
+    /// True once the clock is running or has stopped.
+    pub fn is_started(&self) -> bool {
+        self.start_time.is_some()
+    }
+
+    /// When the clock started, at the first reveal.
+    pub fn started_at(&self) -> Option<Instant> {
+        self.start_time
+    }
+
+    /// Time since the first reveal, frozen once the game is over.
+    pub fn elapsed(&self) -> Duration {
+        match (self.start_time, self.end_time) {
+            (Some(start), Some(end)) => end - start,
+            (Some(start), None) => start.elapsed(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    pub fn quit(&mut self) {
+        self.finish(GameState::Quit);
+    }
+
+    pub fn apply(&mut self, action: &Action) -> Result<Vec<GameEvent>, BoardError> {
+        let playing = !self.is_over();
+        let events = self.perform(action)?;
+        if playing {
+            self.count_click(action, &events)?;
+        }
+        if !events.is_empty() {
+            self.undone.clear();
+        }
+        Ok(events)
+    }
+
+    fn count_click(&mut self, action: &Action, events: &[GameEvent]) -> Result<(), BoardError> {
+        match action {
+            Action::Reveal(_) => self.clicks.left += 1,
+            Action::Flag(_) => self.clicks.right += 1,
+            Action::Chord(_) => self.clicks.chord += 1,
+        }
+        for event in events {
+            let effective = match event {
+                GameEvent::Opened(_) => true,
+                GameEvent::Flagged(pos) => self.board.get(pos)? == ItemType::Landmine,
+                _ => false,
+            };
+            if effective {
+                self.clicks.effective += 1;
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens a closed cell. The first reveal places the landmines.
+    pub fn reveal(&mut self, pos: &Coordinates) -> Result<Vec<GameEvent>, BoardError> {
+        self.apply(&Action::Reveal(*pos))
+    }
+
+    pub fn chord(&mut self, pos: &Coordinates) -> Result<Vec<GameEvent>, BoardError> {
+        self.apply(&Action::Chord(*pos))
+    }
+
+    pub fn toggle_flag(&mut self, pos: &Coordinates) -> Result<Vec<GameEvent>, BoardError> {
+        self.apply(&Action::Flag(*pos))
+    }
+
+    pub fn clicks(&self) -> &Clicks {
+        &self.clicks
+    }
+
+    /// 3BV, clicks and time of the game so far.
+    pub fn efficiency(&self) -> Result<Efficiency, BoardError> {
+        Ok(Efficiency {
+            three_bv: self.board.three_bv()?,
+            clicks: self.clicks,
+            time: self.elapsed(),
+        })
+    }
+
+    pub fn hints_used(&self) -> usize {
+        self.hints_used
+    }
+
+    /// Asks the solver for the next move and counts it as a used hint.
+    /// There is none before the first reveal, which is always safe, or once
+    /// the game is over.
+    pub fn hint(&mut self) -> Result<Option<Hint>, BoardError> {
+        if self.is_over() || !self.landmines_placed {
+            return Ok(None);
+        }
+        let hint = Solver::new(&self.board)?.hint(self.landmine_count);
+        if hint.is_some() {
+            self.hints_used += 1;
+        }
+        Ok(hint)
+    }
+
+    /// The chance of a landmine in every cell given what the player can
+    /// see, row by row.
+    pub fn probabilities(&self) -> Result<Vec<Vec<f64>>, ProbabilityError> {
+        Solver::new(&self.board)?.probabilities(self.landmine_count)
+    }
+
+    pub fn is_practice(&self) -> bool {
+        self.options.practice
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Takes back the last move in practice mode, reopening a game that it
+    /// ended. Returns false when there is nothing to undo.
+    pub fn undo(&mut self) -> Result<bool, BoardError> {
+        let Some(step) = self.history.pop() else {
+            return Ok(false);
+        };
+        for pos in &step.opened {
+            self.board.unclick(pos)?;
+        }
+        for pos in &step.auto_flags {
+            self.board.remove_flag(pos)?;
+        }
+        for pos in &step.questions {
+            self.board.set_mark(pos, Mark::Question)?;
+        }
+        if let Action::Flag(pos) = &step.action {
+            self.board.set_mark(pos, step.mark)?;
+        }
+        if step.finished {
+            self.board.hide_landmines();
+            self.state = GameState::Playing;
+            self.end_time = None;
+        }
+        self.undone.push(step.action);
+        Ok(true)
+    }
+
+    /// Plays the last undone move again. The landmines stay where they
+    /// are, so it has the same outcome as before.
+    pub fn redo(&mut self) -> Result<Vec<GameEvent>, BoardError> {
+        match self.undone.pop() {
+            Some(action) => self.perform(&action),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Plays `action`, keeping it in the history in practice mode.
+    fn perform(&mut self, action: &Action) -> Result<Vec<GameEvent>, BoardError> {
+        self.auto_flags.clear();
+        self.cleared_questions.clear();
+        let mark = match action {
+            Action::Flag(pos) => self.board.mark(pos)?,
+            _ => Mark::None,
+        };
+        let events = match action {
+            Action::Reveal(pos) => self.reveal_cell(pos)?,
+            Action::Flag(pos) => self.flag_cell(pos)?,
+            Action::Chord(pos) => self.chord_cell(pos)?,
+        };
+        if self.options.practice && !events.is_empty() {
+            let opened = events
+                .iter()
+                .filter_map(|event| match event {
+                    GameEvent::Opened(cells) => Some(cells.clone()),
+                    _ => None,
+                })
+                .flatten()
+                .collect();
+            self.history.push(Step {
+                action: action.clone(),
+                mark,
+                opened,
+                auto_flags: std::mem::take(&mut self.auto_flags),
+                questions: std::mem::take(&mut self.cleared_questions),
+                finished: self.is_over(),
+            });
+        }
+        Ok(events)
+    }
+
+    fn reveal_cell(&mut self, pos: &Coordinates) -> Result<Vec<GameEvent>, BoardError> {
+        let mut events = vec![];
+        if self.is_over() || self.board.have_flag(pos)? {
+            return Ok(events);
+        }
+        if !self.landmines_placed {
+            if let Err(e) = self.place_landmines(pos) {
+                events.push(GameEvent::GenerationFailed(e));
+            }
+        }
+        if self.start_time.is_none() {
+            self.start_time = Some(Instant::now());
+        }
+
+        let result = self.board.click(pos)?;
+        self.check_result(result, &mut events)?;
+        Ok(events)
+    }
+
+    fn chord_cell(&mut self, pos: &Coordinates) -> Result<Vec<GameEvent>, BoardError> {
+        let mut events = vec![];
+        if self.is_over() {
+            return Ok(events);
+        }
+        let result = self.board.chord(pos)?;
+        self.check_result(result, &mut events)?;
+        Ok(events)
+    }
+
+    /// Moves the mark on a closed cell along flag, question mark (when
+    /// enabled) and back to nothing.
+    fn flag_cell(&mut self, pos: &Coordinates) -> Result<Vec<GameEvent>, BoardError> {
+        let mut events = vec![];
+        if self.is_over() || self.board.is_opened(pos)? {
+            return Ok(events);
+        }
+        match self.board.mark(pos)? {
+            Mark::None => {
+                self.board.set_mark(pos, Mark::Flag)?;
+                events.push(GameEvent::Flagged(*pos));
+            }
+            Mark::Flag if self.options.question_marks => {
+                self.board.set_mark(pos, Mark::Question)?;
+                events.push(GameEvent::Unflagged(*pos));
+                events.push(GameEvent::Questioned(*pos));
+            }
+            Mark::Flag => {
+                self.board.set_mark(pos, Mark::None)?;
+                events.push(GameEvent::Unflagged(*pos));
+            }
+            Mark::Question => {
+                self.board.set_mark(pos, Mark::None)?;
+                events.push(GameEvent::Unquestioned(*pos));
+            }
+        }
+
+        if self.options.flag_win && self.landmines_placed && self.board.all_landmines_flagged() {
+            self.finish(GameState::Win);
+            events.push(GameEvent::Won);
+        }
+        Ok(events)
+    }
+
+    fn check_result(
+        &mut self,
+        result: ClickResult,
+        events: &mut Vec<GameEvent>,
+    ) -> Result<(), BoardError> {
+        self.cleared_questions = result.questions;
+        if !result.opened.is_empty() {
+            events.push(GameEvent::Opened(result.opened));
+        }
+        if let Some(landmine) = result.landmine {
+            self.board.reveal_landmines(Some(landmine));
+            self.finish(GameState::HitMine);
+            events.push(GameEvent::HitLandmine(landmine));
+        } else if self.board.is_cleared() {
+            self.auto_flags = self.board.flag_landmines()?;
+            self.finish(GameState::Win);
+            events.push(GameEvent::Won);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, state: GameState) {
+        self.state = state;
+        self.end_time = Some(Instant::now());
+    }
+
+    /// Cells kept free of landmines when the first click is at `pos`. Falls
+    /// back to only `pos` when the board is too dense for a full opening.
+    fn safe_cells(&self, pos: &Coordinates) -> Vec<Coordinates> {
+        let config = self.board.get_config();
+        let mut cells = vec![*pos];
+        if self.options.first_click == FirstClick::Opening {
+            let neighbours = pos.neighbours(config.width, config.height);
+            if config.width * config.height - neighbours.len() > self.landmine_count {
+                cells.extend(neighbours);
+            }
+        }
+        cells
+    }
+
+    /// Lays out the landmines around the first click. When the configured
+    /// generator fails a random layout is used and the error is returned.
+    fn place_landmines(&mut self, pos: &Coordinates) -> Result<(), BoardError> {
+        let mut rng = self.seed.rng();
+        let safe_cells = self.safe_cells(pos);
+        let config = self.board.get_config();
+        let generated = self.options.generator.generate(
+            &mut rng,
+            config,
+            self.landmine_count,
+            pos,
+            &safe_cells,
+        );
+        let mines = match &generated {
+            Ok(mines) => mines.clone(),
+            Err(_) => Generator::Random.generate(
+                &mut rng,
+                config,
+                self.landmine_count,
+                pos,
+                &safe_cells,
+            )?,
+        };
+        self.board.set_landmines(&mines)?;
+        self.landmines_placed = true;
+        generated.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crossterm::style::Color;
+
+    /// Options of [`test_engine`], with a fixed seed.
+    pub(crate) fn test_options() -> GameOptions {
+        GameOptions {
+            seed: Some(Seed(7)),
+            ..GameOptions::default()
+        }
+    }
+
+    pub(crate) fn test_engine(width: usize, height: usize, landmine_count: usize) -> Engine {
+        test_engine_with(width, height, landmine_count, test_options())
+    }
+
+    pub(crate) fn test_engine_with(
+        width: usize,
+        height: usize,
+        landmine_count: usize,
+        options: GameOptions,
+    ) -> Engine {
+        let config = BoardConfig::unicode(width, height, Color::Reset);
+        Engine::new(config, landmine_count, options).unwrap()
+    }
+
+    pub(crate) fn test_board(width: usize, height: usize, mines: &[Coordinates]) -> Board {
+        let config = BoardConfig::unicode(width, height, Color::Reset);
+        Board::new(config, mines).unwrap()
+    }
+
+    /// The first cell of the board, row by row, that `pred` holds for.
+    pub(crate) fn find_cell(
+        engine: &Engine,
+        pred: impl Fn(&Board, &Coordinates) -> bool,
+    ) -> Coordinates {
+        let board = engine.board();
+        let config = board.get_config();
+        (0..config.height)
+            .flat_map(|y| (0..config.width).map(move |x| Coordinates::new(x, y)))
+            .find(|pos| pred(board, pos))
+            .expect("no cell matches")
+    }
+
+    #[test]
+    fn too_many_landmines() {
+        let config = BoardConfig::unicode(3, 3, Color::Reset);
+        let result = Engine::new(config, 9, GameOptions::default());
+        assert!(matches!(result, Err(BoardError::TooManyLandmines)));
+    }
+
+    #[test]
+    fn first_reveal_is_safe() {
+        for seed in 0..20 {
+            let config = BoardConfig::unicode(4, 4, Color::Reset);
+            let options = GameOptions {
+                seed: Some(Seed(seed)),
+                ..GameOptions::default()
+            };
+            let mut engine = Engine::new(config, 15, options).unwrap();
+            let events = engine.reveal(&Coordinates::new(2, 2)).unwrap();
+            assert_eq!(
+                events,
+                vec![
+                    GameEvent::Opened(vec![Coordinates::new(2, 2)]),
+                    GameEvent::Won
+                ]
+            );
+            assert_eq!(engine.state(), &GameState::Win);
+        }
+    }
+
+    #[test]
+    fn flag_events() {
+        let mut engine = test_engine(9, 9, 10);
+        let pos = Coordinates::new(0, 0);
+        assert_eq!(
+            engine.apply(&Action::Flag(pos)).unwrap(),
+            vec![GameEvent::Flagged(pos)]
+        );
+        assert_eq!(
+            engine.apply(&Action::Flag(pos)).unwrap(),
+            vec![GameEvent::Unflagged(pos)]
+        );
+    }
+
+    #[test]
+    fn question_marks() {
+        let config = BoardConfig::unicode(9, 9, Color::Reset);
+        let options = GameOptions {
+            seed: Some(Seed(7)),
+            question_marks: true,
+            practice: true,
+            ..GameOptions::default()
+        };
+        let mut engine = Engine::new(config, 10, options).unwrap();
+        let pos = Coordinates::new(4, 4);
+        engine.toggle_flag(&pos).unwrap();
+        assert_eq!(
+            engine.toggle_flag(&pos).unwrap(),
+            vec![GameEvent::Unflagged(pos), GameEvent::Questioned(pos)]
+        );
+        assert_eq!(engine.board().mark(&pos).unwrap(), Mark::Question);
+        assert_eq!(engine.remaining_landmines(), 10);
+
+        // Undo puts the flag back, redo the question mark.
+        engine.undo().unwrap();
+        assert_eq!(engine.board().mark(&pos).unwrap(), Mark::Flag);
+        engine.redo().unwrap();
+
+        // A question mark does not stop a reveal and is cleared by it.
+        let events = engine.reveal(&pos).unwrap();
+        assert!(matches!(&events[0], GameEvent::Opened(cells) if cells.contains(&pos)));
+        assert_eq!(engine.board().mark(&pos).unwrap(), Mark::None);
+    }
+
+    #[test]
+    fn hints_are_counted() {
+        let mut engine = test_engine(9, 9, 10);
+        assert_eq!(engine.hint().unwrap(), None);
+        engine.reveal(&Coordinates::new(4, 4)).unwrap();
+        let hint = engine.hint().unwrap().unwrap();
+        assert!(!engine.board().is_opened(&hint.pos).unwrap());
+        assert_eq!(engine.hints_used(), 1);
+    }
+
+    #[test]
+    fn clicks_are_counted() {
+        let mut engine = test_engine(9, 9, 10);
+        let start = Coordinates::new(4, 4);
+        engine.reveal(&start).unwrap();
+        engine.reveal(&start).unwrap();
+        engine.chord(&start).unwrap();
+        let closed = |landmine| {
+            find_cell(&engine, |board, pos| {
+                !board.is_opened(pos).unwrap()
+                    && (board.get(pos).unwrap() == ItemType::Landmine) == landmine
+            })
+        };
+        let (landmine, safe) = (closed(true), closed(false));
+        engine.toggle_flag(&landmine).unwrap();
+        engine.toggle_flag(&safe).unwrap();
+
+        let clicks = Clicks {
+            left: 2,
+            right: 2,
+            chord: 1,
+            effective: 2,
+        };
+        assert_eq!(engine.clicks(), &clicks);
+        assert_eq!(clicks.correctness(), Some(0.4));
+        let efficiency = engine.efficiency().unwrap();
+        assert_eq!(efficiency.three_bv, engine.board().three_bv().unwrap());
+        assert_eq!(efficiency.ioe(), Some(efficiency.three_bv as f64 / 5.0));
+
+        // Clicks on a finished game do not count.
+        engine.toggle_flag(&landmine).unwrap();
+        engine.reveal(&landmine).unwrap();
+        assert!(engine.is_over());
+        engine.toggle_flag(&safe).unwrap();
+        engine.reveal(&safe).unwrap();
+        assert_eq!(engine.clicks().total(), 7);
+    }
+
+    #[test]
+    fn remaining_landmines_counts_flags() {
+        let mut engine = test_engine(9, 9, 10);
+        assert_eq!(engine.remaining_landmines(), 10);
+        engine.toggle_flag(&Coordinates::new(0, 0)).unwrap();
+        engine.toggle_flag(&Coordinates::new(1, 0)).unwrap();
+        assert_eq!(engine.remaining_landmines(), 8);
+    }
+
+    #[test]
+    fn hitting_a_landmine_ends_the_game() {
+        let mut engine = test_engine(9, 9, 10);
+        engine.reveal(&Coordinates::new(4, 4)).unwrap();
+
+        let landmine = find_cell(&engine, |board, pos| {
+            board.get(pos).unwrap() == ItemType::Landmine
+        });
+        let events = engine.reveal(&landmine).unwrap();
+        assert_eq!(events.last(), Some(&GameEvent::HitLandmine(landmine)));
+        assert_eq!(engine.state(), &GameState::HitMine);
+        assert!(engine.reveal(&Coordinates::new(0, 0)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn undo_puts_back_cleared_question_marks() {
+        let options = GameOptions {
+            practice: true,
+            question_marks: true,
+            ..test_options()
+        };
+        let mut engine = test_engine_with(9, 9, 10, options);
+        engine.reveal(&Coordinates::new(4, 4)).unwrap();
+        let unsure = find_cell(&engine, |board, pos| {
+            !board.is_opened(pos).unwrap() && board.get(pos).unwrap() != ItemType::Landmine
+        });
+        engine.toggle_flag(&unsure).unwrap();
+        engine.toggle_flag(&unsure).unwrap();
+        assert_eq!(engine.board().mark(&unsure).unwrap(), Mark::Question);
+
+        engine.reveal(&unsure).unwrap();
+        assert_eq!(engine.board().mark(&unsure).unwrap(), Mark::None);
+        assert!(engine.undo().unwrap());
+        assert!(!engine.board().is_opened(&unsure).unwrap());
+        assert_eq!(engine.board().mark(&unsure).unwrap(), Mark::Question);
+    }
+
+    #[test]
+    fn practice_undoes_a_losing_click() {
+        let options = GameOptions {
+            practice: true,
+            ..test_options()
+        };
+        let mut engine = test_engine_with(9, 9, 10, options);
+        engine.reveal(&Coordinates::new(4, 4)).unwrap();
+        let opened = *engine.board().opened_count();
+        let flag = Coordinates::new(0, 0);
+        engine.toggle_flag(&flag).unwrap();
+
+        let landmine = find_cell(&engine, |board, pos| {
+            *pos != flag && board.get(pos).unwrap() == ItemType::Landmine
+        });
+        engine.reveal(&landmine).unwrap();
+        assert_eq!(engine.state(), &GameState::HitMine);
+
+        assert!(engine.undo().unwrap());
+        assert_eq!(engine.state(), &GameState::Playing);
+        assert_eq!(*engine.board().opened_count(), opened);
+        assert!(engine.undo().unwrap());
+        assert!(!engine.board().have_flag(&flag).unwrap());
+
+        assert_eq!(engine.redo().unwrap(), vec![GameEvent::Flagged(flag)]);
+        assert_eq!(
+            engine.redo().unwrap().last(),
+            Some(&GameEvent::HitLandmine(landmine))
+        );
+        assert!(!engine.can_redo());
+
+        // A new move drops the moves that were undone.
+        engine.undo().unwrap();
+        engine.toggle_flag(&landmine).unwrap();
+        assert!(!engine.can_redo());
+    }
+
+    #[test]
+    fn history_is_only_kept_in_practice() {
+        let mut engine = test_engine(9, 9, 10);
+        engine.reveal(&Coordinates::new(4, 4)).unwrap();
+        assert!(!engine.can_undo());
+        assert!(!engine.undo().unwrap());
+    }
+}
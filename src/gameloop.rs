@@ -1,30 +1,28 @@
 #![cfg(feature = "bracketed-paste")]
-use crate::mineboard::{Board, BoardConfig, BoardError, ItemType};
-use crate::utils::Coordinates;
+use crate::engine::{Engine, GameEvent};
+use crate::mineboard::{BoardConfig, BoardError};
+use crate::replay::{self, Recorder, ReplayAction};
+use crate::save;
+use crate::settings::{key_name, KeyAction, KeyBindings, Settings};
+use crate::solver::{Hint, HintKind, ProbabilityError};
+use crate::stats;
+use crate::utils::{Coordinates, Seed};
 use crossterm::cursor::{self, EnableBlinking};
-use crossterm::event::{
-    KeyCode, KeyEvent, KeyModifiers, ModifierKeyCode, MouseButton, MouseEvent, MouseEventKind,
-};
-use crossterm::style::Color;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::style::Print;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
 use crossterm::{
-    event::{
-        read, DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
-        EnableFocusChange, EnableMouseCapture, Event,
-    },
+    event::{poll, read, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
 };
 
-use rand::prelude::*;
 use std::io::stdout;
+use std::time::Duration;
 
-#[derive(Clone, Debug)]
-pub enum GameState {
-    Playing,
-    Win,
-    HitMine,
-    Quit,
-}
+pub use crate::engine::{FirstClick, GameOptions, GameState};
+
+/// How often the status line is refreshed while waiting for input.
+const TICK: Duration = Duration::from_millis(200);
 
 #[derive(Clone, Debug)]
 pub enum Direction {
@@ -34,21 +32,74 @@ pub enum Direction {
     Right,
 }
 
+/// Terminal frontend for an [`Engine`].
 #[derive(Clone, Debug)]
 pub struct Game {
-    board: Board,
-    state: GameState,
+    engine: Engine,
+    /// The first mouse button held down. Its click happens on release, so
+    /// that pressing the other button as well can chord instead.
+    mouse_held: Option<MouseButton>,
+    /// Set when the held buttons already chorded.
+    chorded: bool,
+    message: Option<String>,
+    keys: KeyBindings,
+    /// Set when a finished practice game is left.
+    leave: bool,
+    /// Draw the chance of a landmine over the closed cells.
+    analysis: bool,
+    /// The chances last worked out, until a move changes the board.
+    heatmap: Option<Result<Vec<Vec<f64>>, ProbabilityError>>,
+    recorder: Recorder,
+    /// Set when the game was resumed from the save, which is then removed
+    /// once the game is finished.
+    resumed: bool,
 }
 
 impl Game {
-    pub fn new(config: BoardConfig, landmine_count: usize) -> Result<Self, BoardError> {
-        let mines = Self::gen_landmine(landmine_count, &config);
-        let mut board: Board = Board::new(config, &mines)?;
-        board.draw_border();
-        Ok(Self {
-            board,
-            state: GameState::Playing,
-        })
+    pub fn new(
+        config: BoardConfig,
+        landmine_count: usize,
+        options: GameOptions,
+    ) -> Result<Self, BoardError> {
+        let engine = Engine::new(config, landmine_count, options)?;
+        Ok(Self::from_engine(engine))
+    }
+
+    pub fn from_engine(mut engine: Engine) -> Self {
+        engine.board_mut().draw_border();
+        Self {
+            recorder: Recorder::new(&engine),
+            engine,
+            mouse_held: None,
+            chorded: false,
+            message: None,
+            keys: KeyBindings::default(),
+            leave: false,
+            analysis: false,
+            heatmap: None,
+            resumed: false,
+        }
+    }
+
+    pub fn with_keys(mut self, keys: KeyBindings) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    pub fn state(&self) -> &GameState {
+        self.engine.state()
+    }
+
+    pub fn seed(&self) -> &Seed {
+        self.engine.seed()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.engine.elapsed()
     }
 
     pub fn game_loop(&mut self) {
@@ -60,10 +111,32 @@ impl Game {
         self.rander();
         execute!(stdout, cursor::MoveTo(2, 1)).unwrap();
         loop {
-            self.get_and_proccess_input();
-            self.rander();
-            match self.state {
-                GameState::Quit | GameState::HitMine | GameState::Win => break,
+            if self.get_and_proccess_input() {
+                self.rander();
+            } else {
+                self.rander_status();
+            }
+            match self.state() {
+                GameState::Quit => {
+                    self.save();
+                    break;
+                }
+                // A finished practice game stays open so the last move can
+                // be undone.
+                GameState::HitMine | GameState::Win if self.engine.is_practice() => {
+                    if self.leave {
+                        self.save_replay();
+                        self.remove_save();
+                        break;
+                    }
+                }
+                GameState::HitMine | GameState::Win => {
+                    self.save_replay();
+                    self.remove_save();
+                    self.record_stats();
+                    self.show_result();
+                    break;
+                }
                 GameState::Playing => {}
             }
         }
@@ -71,25 +144,118 @@ impl Game {
         disable_raw_mode().unwrap();
     }
 
-    fn gen_landmine(landmine_count: usize, config: &BoardConfig) -> Vec<Coordinates> {
-        let mut rng = rand::thread_rng();
+    /// Keeps a started game so it can be resumed on the next run.
+    fn save(&self) {
+        if !self.engine.landmines_placed() {
+            return;
+        }
+        if let Some(path) = save::default_path() {
+            // The terminal is still in raw mode, so a failed save can only
+            // be dropped here.
+            let _ = save::save(&self.engine, &path);
+        }
+    }
 
-        let mut mines = vec![];
+    /// Removes the save a finished game was resumed from, so it is not
+    /// resumed again.
+    fn remove_save(&self) {
+        if let Some(path) = save::default_path().filter(|_| self.resumed) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
 
-        let height = config.height;
-        let width = config.width;
+    /// Keeps the finished game so it can be watched again.
+    fn save_replay(&self) {
+        let replay = match self.recorder.finish(&self.engine) {
+            Ok(Some(replay)) => replay,
+            _ => return,
+        };
+        if let Some(dir) = replay::default_dir() {
+            // Dropped like a failed save.
+            let _ = replay::save(&replay, &dir);
+        }
+    }
 
-        for _ in 0..landmine_count {
-            let pos = loop {
-                let pos = Coordinates::new(rng.gen_range(0..width), rng.gen_range(0..height));
-                if mines.iter().find(|&&p| p == pos).is_some() {
-                    continue;
+    /// Counts the finished game in the lifetime statistics. Practice games
+    /// are finished by leaving them and are not counted.
+    fn record_stats(&self) {
+        if let Some(path) = stats::default_path() {
+            // Like a failed save, a failed update cannot be shown in raw mode.
+            let _ = stats::record(&path, &self.engine);
+        }
+    }
+
+    fn show_result(&mut self) {
+        let mut stdout = stdout();
+        let outcome = match self.state() {
+            GameState::Win => "You win!",
+            GameState::HitMine => "You hit a landmine!",
+            _ => return,
+        };
+        let result = format!(
+            "{} Time: {:.1}s Hints: {}",
+            outcome,
+            self.elapsed().as_secs_f64(),
+            self.engine.hints_used()
+        );
+        let efficiency = self.describe_efficiency();
+        let help = format!(
+            "{}: show landmine chances  Any other key: continue",
+            key_name(self.keys.analysis)
+        );
+
+        loop {
+            let row = self.status_row(2);
+            execute!(
+                stdout,
+                cursor::MoveTo(0, row),
+                Print(&result),
+                cursor::MoveTo(0, row + 1),
+                Print(&efficiency),
+                cursor::MoveTo(0, row + 2),
+                Print(&help),
+            )
+            .unwrap();
+            match self.get_input().unwrap() {
+                // Looking back at the chances is what the analysis is for.
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(c),
+                    ..
+                }) if self.keys.action(c) == Some(KeyAction::Analysis) => {
+                    self.analysis = !self.analysis;
+                    self.rander();
                 }
-                break pos;
-            };
-            mines.push(pos);
+                Event::Key(_) => break,
+                _ => (),
+            }
+        }
+    }
+
+    /// Clicks and correctness, and for a win how fast and efficiently the
+    /// board was cleared.
+    fn describe_efficiency(&self) -> String {
+        let Ok(efficiency) = self.engine.efficiency() else {
+            return String::new();
+        };
+        let clicks = efficiency.clicks;
+        let mut text = format!(
+            "Clicks: {} ({} left, {} right, {} chord)  Correctness: {:.0}%",
+            clicks.total(),
+            clicks.left,
+            clicks.right,
+            clicks.chord,
+            clicks.correctness().unwrap_or(0.0) * 100.0
+        );
+        if self.state() == &GameState::Win {
+            text = format!(
+                "3BV: {}  3BV/s: {:.2}  IOE: {:.2}  {}",
+                efficiency.three_bv,
+                efficiency.three_bv_per_second().unwrap_or(0.0),
+                efficiency.ioe().unwrap_or(0.0),
+                text
+            );
         }
-        mines
+        text
     }
 
     fn rander(&mut self) {
@@ -101,12 +267,91 @@ impl Game {
             cursor::MoveTo(0, 0)
         )
         .unwrap();
-        self.board.print();
+        let status = self.status();
+        let heatmap = if self.analysis {
+            match self
+                .heatmap
+                .get_or_insert_with(|| self.engine.probabilities())
+            {
+                Ok(heatmap) => Some(heatmap.clone()),
+                Err(e) => {
+                    self.message = Some(e.to_string());
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let board = self.engine.board_mut();
+        board.set_heatmap(heatmap);
+        board.draw_status(&status);
+        board.print();
+        if let Some(message) = &self.message {
+            execute!(
+                stdout,
+                cursor::MoveTo(0, self.status_row(1)),
+                Print(message)
+            )
+            .unwrap();
+        }
+        if self.engine.is_practice() {
+            let help = format!(
+                "PRACTICE  {}: undo  {}: redo  {}: landmine chances  {}: leave",
+                key_name(self.keys.undo),
+                key_name(self.keys.redo),
+                key_name(self.keys.analysis),
+                key_name(self.keys.quit)
+            );
+            execute!(stdout, cursor::MoveTo(0, self.status_row(2)), Print(help)).unwrap();
+        }
         execute!(stdout, cursor::RestorePosition).unwrap();
     }
 
+    /// Redraws only the status line, so the clock keeps ticking between
+    /// inputs.
+    fn rander_status(&mut self) {
+        let mut stdout = stdout();
+        let status = self.status();
+        self.engine.board_mut().draw_status(&status);
+        execute!(
+            stdout,
+            cursor::SavePosition,
+            cursor::MoveTo(0, self.status_row(0)),
+            Clear(ClearType::CurrentLine),
+            Print(status),
+            cursor::RestorePosition
+        )
+        .unwrap();
+    }
+
+    fn status(&self) -> String {
+        let state = match self.state() {
+            GameState::Playing if !self.engine.is_started() => "Ready",
+            GameState::Playing => "Playing",
+            GameState::Win => "Won",
+            GameState::HitMine => "Lost",
+            GameState::Quit => "Quit",
+        };
+        let mut status = format!(
+            "💣 {:<4} ⏱ {:<5} {:<8} Seed: {}",
+            self.engine.remaining_landmines(),
+            self.elapsed().as_secs(),
+            state,
+            self.seed()
+        );
+        if self.engine.is_practice() {
+            status += "  PRACTICE";
+        }
+        status
+    }
+
+    /// Terminal row of the `line`th line below the board.
+    fn status_row(&self, line: u16) -> u16 {
+        self.engine.board().get_config().height as u16 + 2 + line
+    }
+
     pub fn landmine_count(&self) -> &usize {
-        self.board.landmine_count()
+        self.engine.landmine_count()
     }
 
     fn get_input(&self) -> std::io::Result<Event> {
@@ -114,65 +359,63 @@ impl Game {
         Ok(key_event)
     }
 
-    fn get_and_proccess_input(&mut self) {
+    /// Waits up to one tick for input and handles it. Returns false when
+    /// nothing was handled.
+    fn get_and_proccess_input(&mut self) -> bool {
         let mut stdout = stdout();
-        loop {
+        while poll(TICK).unwrap() {
             match self.get_input().unwrap() {
                 Event::Mouse(mouse_event) => {
                     self.process_mouse(&mut stdout, mouse_event);
-                    break;
+                    return true;
                 }
                 Event::Key(key_event) => {
                     self.process_key(&mut stdout, key_event);
-                    break;
+                    return true;
                 }
                 _ => (),
             }
         }
+        false
     }
 
     fn process_key(&mut self, stdout: &mut std::io::Stdout, key_event: KeyEvent) {
-        if key_event.modifiers != KeyModifiers::NONE {
+        if key_event.modifiers.difference(KeyModifiers::SHIFT) != KeyModifiers::NONE {
             return;
         }
 
-        let pos = match key_event.code {
-            KeyCode::Char('W') | KeyCode::Char('w') | KeyCode::Up => {
-                self.update_cursor_location(stdout, Direction::Up);
-                return;
-            }
-            KeyCode::Char('S') | KeyCode::Char('s') | KeyCode::Down => {
-                self.update_cursor_location(stdout, Direction::Down);
-                return;
-            }
-            KeyCode::Char('D') | KeyCode::Char('d') | KeyCode::Right => {
-                self.update_cursor_location(stdout, Direction::Right);
-                return;
-            }
-            KeyCode::Char('A') | KeyCode::Char('a') | KeyCode::Left => {
-                self.update_cursor_location(stdout, Direction::Left);
-                return;
-            }
-            KeyCode::Char('Q') | KeyCode::Char('q') => {
-                self.state = GameState::Quit;
-                return;
-            }
-            _ => match self.get_coordinates_from_cursor() {
-                Some(p) => p,
-                _ => return,
-            },
+        let action = match key_event.code {
+            KeyCode::Up => Some(KeyAction::Up),
+            KeyCode::Down => Some(KeyAction::Down),
+            KeyCode::Right => Some(KeyAction::Right),
+            KeyCode::Left => Some(KeyAction::Left),
+            KeyCode::Enter => Some(KeyAction::Reveal),
+            KeyCode::Char(c) => self.keys.action(c),
+            _ => None,
         };
 
-        match key_event.code {
-            KeyCode::Enter | KeyCode::Char(' ') => {
-                self.click(&pos);
-                return;
+        match action {
+            Some(KeyAction::Up) => self.update_cursor_location(stdout, Direction::Up),
+            Some(KeyAction::Down) => self.update_cursor_location(stdout, Direction::Down),
+            Some(KeyAction::Right) => self.update_cursor_location(stdout, Direction::Right),
+            Some(KeyAction::Left) => self.update_cursor_location(stdout, Direction::Left),
+            Some(KeyAction::Quit) if self.engine.is_over() => self.leave = true,
+            Some(KeyAction::Quit) => self.engine.quit(),
+            Some(KeyAction::Hint) => self.show_hint(stdout),
+            Some(KeyAction::Analysis) => self.toggle_analysis(),
+            Some(KeyAction::Undo) => self.undo(),
+            Some(KeyAction::Redo) => self.redo(),
+            Some(KeyAction::Reveal) => {
+                if let Some(pos) = self.get_coordinates_from_cursor() {
+                    self.click(&pos);
+                }
             }
-            KeyCode::Char('F') | KeyCode::Char('f') => {
-                self.click_flag(&pos);
-                return;
+            Some(KeyAction::Flag) => {
+                if let Some(pos) = self.get_coordinates_from_cursor() {
+                    self.click_flag(&pos);
+                }
             }
-            _ => return,
+            None => (),
         }
     }
 
@@ -186,53 +429,152 @@ impl Game {
 
         let pos = match self.get_coordinates_from_cursor() {
             Some(pos) => pos,
-            None => return,
+            None => {
+                // Releasing off the board lets go without clicking.
+                if let MouseEventKind::Up(button) = mouse_event.kind {
+                    if self.mouse_held == Some(button) {
+                        self.mouse_held = None;
+                    }
+                }
+                return;
+            }
         };
+        self.recorder.record(ReplayAction::Move(pos));
 
-        let button_pressed = match mouse_event.kind {
-            MouseEventKind::Down(b) => b,
-            _ => return,
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Middle) => self.chord(&pos),
+            // Pressing left and right together chords, like the middle
+            // button, and neither button clicks on its own.
+            MouseEventKind::Down(button) => match self.mouse_held {
+                Some(held) if held != button => {
+                    if !self.chorded {
+                        self.chorded = true;
+                        self.chord(&pos);
+                    }
+                }
+                _ => {
+                    self.mouse_held = Some(button);
+                    self.chorded = false;
+                }
+            },
+            MouseEventKind::Up(button) if self.mouse_held == Some(button) => {
+                self.mouse_held = None;
+                match button {
+                    _ if self.chorded => (),
+                    MouseButton::Left => self.click(&pos),
+                    MouseButton::Right => self.click_flag(&pos),
+                    MouseButton::Middle => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn click_flag(&mut self, pos: &Coordinates) {
+        self.recorder.record(ReplayAction::Flag(*pos));
+        let events = self.engine.toggle_flag(pos).unwrap();
+        self.handle_events(events);
+    }
+
+    /// Opens a closed cell, or chords an opened one.
+    fn click(&mut self, pos: &Coordinates) {
+        if self.engine.board().is_opened(pos).unwrap() {
+            self.chord(pos);
+            return;
+        }
+        self.recorder.record(ReplayAction::Reveal(*pos));
+        let events = self.engine.reveal(pos).unwrap();
+        self.handle_events(events);
+    }
+
+    fn chord(&mut self, pos: &Coordinates) {
+        self.recorder.record(ReplayAction::Chord(*pos));
+        let events = self.engine.chord(pos).unwrap();
+        self.handle_events(events);
+    }
+
+    /// Points out the cell the solver would play next and says why.
+    fn show_hint(&mut self, stdout: &mut std::io::Stdout) {
+        let hint = match self.engine.hint().unwrap() {
+            Some(hint) => hint,
+            None if !self.engine.landmines_placed() => {
+                self.message = Some("Open any cell, the first one is always safe".to_string());
+                return;
+            }
+            None => return,
         };
+        let size = self.engine.board().get_config().char_width as usize;
+        let (x, y) = ((hint.pos.x + 1) * size, hint.pos.y + 1);
+        execute!(stdout, cursor::MoveTo(x as u16, y as u16)).unwrap();
+        self.recorder.record(ReplayAction::Move(hint.pos));
+        self.engine.board_mut().set_highlight(Some(hint.pos));
+        self.message = Some(describe_hint(&hint));
+    }
 
-        match button_pressed {
-            MouseButton::Left => self.click(&pos),
-            MouseButton::Right => self.click_flag(&pos),
-            _ => return,
+    /// The chances give away the board, so they can only be looked at in
+    /// practice or once the game is over.
+    fn toggle_analysis(&mut self) {
+        if !self.engine.is_practice() && !self.engine.is_over() {
+            self.message = Some("Landmine chances are only shown in practice mode".to_string());
+            return;
         }
+        self.analysis = !self.analysis;
     }
 
-    fn click_flag(&mut self, pos: &Coordinates) {
-        if self.board.have_flag(pos).unwrap() {
-            self.board.remove_flag(pos).unwrap();
-        } else {
-            self.board.add_flag(pos).unwrap();
+    fn undo(&mut self) {
+        if !self.engine.is_practice() {
+            self.message = Some("Undo is only available in practice mode".to_string());
+            return;
         }
+        self.recorder.record(ReplayAction::Undo);
+        self.engine.undo().unwrap();
+        self.heatmap = None;
     }
 
-    fn click(&mut self, pos: &Coordinates) {
-        if self.board.get(pos).unwrap() == ItemType::Landmine {
-            self.state = GameState::HitMine;
+    fn redo(&mut self) {
+        if !self.engine.is_practice() {
+            self.message = Some("Redo is only available in practice mode".to_string());
+            return;
+        }
+        self.recorder.record(ReplayAction::Redo);
+        let events = self.engine.redo().unwrap();
+        self.handle_events(events);
+    }
+
+    fn handle_events(&mut self, events: Vec<GameEvent>) {
+        // A hint is about the board before this action.
+        self.engine.board_mut().set_highlight(None);
+        self.message = None;
+        if !events.is_empty() {
+            self.heatmap = None;
+        }
+        for event in events {
+            if let GameEvent::GenerationFailed(e) = event {
+                self.message = Some(format!("{}, playing a random board", e));
+            }
         }
-        self.board.click(pos).unwrap();
     }
 
-    fn update_cursor_location(&self, stdout: &mut std::io::Stdout, dir: Direction) {
-        let size = self.board.get_config().char_width as u16;
+    fn update_cursor_location(&mut self, stdout: &mut std::io::Stdout, dir: Direction) {
+        let size = self.engine.board().get_config().char_width as u16;
         match dir {
             Direction::Up => execute!(stdout, cursor::MoveUp(1)).unwrap(),
             Direction::Down => execute!(stdout, cursor::MoveDown(1)).unwrap(),
             Direction::Left => execute!(stdout, cursor::MoveLeft(size)).unwrap(),
             Direction::Right => execute!(stdout, cursor::MoveRight(size)).unwrap(),
         }
+        if let Some(pos) = self.get_coordinates_from_cursor() {
+            self.recorder.record(ReplayAction::Move(pos));
+        }
     }
 
     pub fn pos_in_range(&self, x: u32, y: u32) -> bool {
-        let config = self.board.get_config();
+        let config = self.engine.board().get_config();
         x < config.width as u32 && y < config.height as u32
     }
 
     fn terminal_pos_to_coordinates(&self, x: u16, y: u16) -> Option<Coordinates> {
-        let config = self.board.get_config();
+        let config = self.engine.board().get_config();
         let x = x as usize;
         let y = y as usize;
         if x < 2 || y < 1 {
@@ -259,8 +601,46 @@ impl Game {
     }
 }
 
-pub fn test1() {
-    let board_config = BoardConfig::unicode(20, 20, Color::Reset);
-    let mut game = Game::new(board_config, 20).unwrap();
-    game.game_loop();
+/// Explains a hint, naming cells by column and row counted from 1.
+fn describe_hint(hint: &Hint) -> String {
+    let cell = |pos: &Coordinates| format!("{},{}", pos.x + 1, pos.y + 1);
+    let reasons = match hint.reasons.len() {
+        0 => "the number of landmines left forces it".to_string(),
+        1 => format!("the number at {} forces it", cell(&hint.reasons[0])),
+        _ => format!(
+            "the numbers at {} force it",
+            hint.reasons
+                .iter()
+                .map(cell)
+                .collect::<Vec<_>>()
+                .join(" and ")
+        ),
+    };
+    match hint.kind {
+        HintKind::Safe => format!("{} is safe: {}", cell(&hint.pos), reasons),
+        HintKind::Landmine => format!("{} is a landmine: {}", cell(&hint.pos), reasons),
+        HintKind::Guess(risk) => format!(
+            "Nothing is certain. Safest guess: {} ({:.0}% risk)",
+            cell(&hint.pos),
+            risk * 100.0
+        ),
+    }
+}
+
+/// Loads the game saved by the last run, if there is one. The save stays
+/// until the game is finished, so it survives a crash. A save that cannot
+/// be read is kept too, and the error says why.
+pub fn resume(settings: &Settings) -> Result<Option<Game>, String> {
+    let Some(path) = save::default_path() else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut engine =
+        save::load(&path).map_err(|e| format!("could not resume {}: {}", path.display(), e))?;
+    settings.apply(engine.board_mut());
+    let mut game = Game::from_engine(engine).with_keys(settings.keys.clone());
+    game.resumed = true;
+    Ok(Some(game))
 }
@@ -0,0 +1,249 @@
+use crate::mineboard::{Board, BoardConfig, BoardError};
+use crate::solver::solvable_without_guessing;
+use crate::utils::{Coordinates, ParseOptionError};
+use rand::RngCore;
+use std::fmt;
+use std::str::FromStr;
+
+/// How landmines are laid out once the first cell is clicked.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Generator {
+    /// Any layout that keeps the safe cells free.
+    #[default]
+    Random,
+    /// Only layouts that can be cleared from the first click by deduction
+    /// alone. Gives up after `attempts` random layouts.
+    NoGuess { attempts: usize },
+}
+
+impl Generator {
+    /// Places `landmine_count` landmines on a board described by `config`,
+    /// leaving `safe_cells` free. `start` is the first cell clicked.
+    ///
+    /// A random layout only depends on `rng`: landmines that land on a safe
+    /// cell are moved to the next free cells in the same random order. A
+    /// no-guess layout also depends on `start`, since it has to be
+    /// solvable from there.
+    pub fn generate<R: RngCore>(
+        &self,
+        rng: &mut R,
+        config: &BoardConfig,
+        landmine_count: usize,
+        start: &Coordinates,
+        safe_cells: &[Coordinates],
+    ) -> Result<Vec<Coordinates>, BoardError> {
+        match self {
+            Generator::Random => Ok(random_landmines(rng, config, landmine_count, safe_cells)),
+            Generator::NoGuess { attempts } => {
+                for _ in 0..*attempts {
+                    let mines = random_landmines(rng, config, landmine_count, safe_cells);
+                    let mut board = Board::new(config.clone(), &mines)?;
+                    if solvable_without_guessing(&mut board, start)? {
+                        return Ok(mines);
+                    }
+                }
+                Err(BoardError::GenerationFailed(*attempts))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Generator::Random => write!(f, "random"),
+            Generator::NoGuess { attempts } => write!(f, "no-guess:{}", attempts),
+        }
+    }
+}
+
+impl FromStr for Generator {
+    type Err = ParseOptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "random" => Ok(Generator::Random),
+            Some(("no-guess", attempts)) => attempts
+                .parse()
+                .map(|attempts| Generator::NoGuess { attempts })
+                .map_err(|_| ParseOptionError(s.to_string())),
+            _ => Err(ParseOptionError(s.to_string())),
+        }
+    }
+}
+
+fn random_landmines<R: RngCore>(
+    rng: &mut R,
+    config: &BoardConfig,
+    landmine_count: usize,
+    safe_cells: &[Coordinates],
+) -> Vec<Coordinates> {
+    let mut cells = vec![];
+    for y in 0..config.height {
+        for x in 0..config.width {
+            cells.push(Coordinates::new(x, y));
+        }
+    }
+    shuffle(rng, &mut cells);
+
+    let (mines, rest) = cells.split_at(landmine_count.min(cells.len()));
+    let mut spare = rest.iter().filter(|pos| !safe_cells.contains(pos));
+    mines
+        .iter()
+        .filter_map(|pos| match safe_cells.contains(pos) {
+            true => spare.next(),
+            false => Some(pos),
+        })
+        .copied()
+        .collect()
+}
+
+/// Fisher-Yates shuffle on the raw output of `rng`, so the order does not
+/// depend on how a version of `rand` maps numbers into a range.
+fn shuffle<T, R: RngCore>(rng: &mut R, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        let bound = i as u64 + 1;
+        // Rejecting the top of the range keeps every index equally likely.
+        let zone = u64::MAX - u64::MAX % bound;
+        let j = loop {
+            let value = rng.next_u64();
+            if value < zone {
+                break value % bound;
+            }
+        };
+        items.swap(i, j as usize);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::Seed;
+    use crossterm::style::Color;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn random_keeps_safe_cells_free() {
+        let config = BoardConfig::unicode(4, 4, Color::Reset);
+        let start = Coordinates::new(0, 0);
+        let safe_cells = start.neighbours(4, 4);
+        let mines = Generator::Random
+            .generate(
+                &mut StdRng::seed_from_u64(7),
+                &config,
+                12,
+                &start,
+                &safe_cells,
+            )
+            .unwrap();
+
+        assert_eq!(mines.len(), 12);
+        assert!(mines.iter().all(|pos| !safe_cells.contains(pos)));
+    }
+
+    #[test]
+    fn same_seed_gives_same_layout() {
+        let config = BoardConfig::unicode(16, 16, Color::Reset);
+        let start = Coordinates::new(3, 3);
+        let generate = |seed| {
+            Generator::Random
+                .generate(
+                    &mut StdRng::seed_from_u64(seed),
+                    &config,
+                    40,
+                    &start,
+                    &[start],
+                )
+                .unwrap()
+        };
+
+        assert_eq!(generate(42), generate(42));
+        assert_ne!(generate(42), generate(43));
+    }
+
+    #[test]
+    fn no_guess_board_is_solvable() {
+        let config = BoardConfig::unicode(9, 9, Color::Reset);
+        let start = Coordinates::new(4, 4);
+        let mut safe_cells = start.neighbours(9, 9);
+        safe_cells.push(start);
+        let mines = Generator::NoGuess { attempts: 1000 }
+            .generate(
+                &mut StdRng::seed_from_u64(7),
+                &config,
+                10,
+                &start,
+                &safe_cells,
+            )
+            .unwrap();
+
+        let mut board = Board::new(config, &mines).unwrap();
+        assert!(solvable_without_guessing(&mut board, &start).unwrap());
+    }
+
+    #[test]
+    fn random_layout_only_depends_on_the_seed() {
+        let config = BoardConfig::unicode(9, 9, Color::Reset);
+        let generate = |safe_cells: &[Coordinates]| {
+            Generator::Random
+                .generate(&mut Seed(42).rng(), &config, 10, &safe_cells[0], safe_cells)
+                .unwrap()
+        };
+        let layout = generate(&[Coordinates::new(0, 0), Coordinates::new(8, 8)]);
+        // Pinned, so a change to the layout of a seed is noticed.
+        let pinned = [
+            (3, 5),
+            (8, 2),
+            (3, 8),
+            (2, 6),
+            (8, 4),
+            (4, 1),
+            (4, 8),
+            (1, 5),
+            (2, 3),
+            (4, 2),
+        ];
+        let pinned: Vec<Coordinates> = pinned
+            .iter()
+            .map(|&(x, y)| Coordinates::new(x, y))
+            .collect();
+        assert_eq!(layout, pinned);
+
+        // Any first click on a free cell gets the same board.
+        let free: Vec<Coordinates> = (0..81)
+            .map(|i| Coordinates::new(i % 9, i / 9))
+            .filter(|pos| !layout.contains(pos))
+            .collect();
+        assert_eq!(generate(&[free[0]]), generate(&[free[40]]));
+
+        // A landmine under the first click moves, the rest stay.
+        let moved = generate(&[layout[0]]);
+        assert_eq!(moved.len(), 10);
+        assert!(!moved.contains(&layout[0]));
+        assert!(layout[1..].iter().all(|pos| moved.contains(pos)));
+    }
+
+    #[test]
+    fn parse_generator() {
+        for generator in [Generator::Random, Generator::NoGuess { attempts: 100 }] {
+            assert_eq!(generator.to_string().parse(), Ok(generator));
+        }
+        assert!("no-guess:lots".parse::<Generator>().is_err());
+    }
+
+    #[test]
+    fn no_guess_reports_failure() {
+        let config = BoardConfig::unicode(2, 2, Color::Reset);
+        let start = Coordinates::new(0, 0);
+        let result = Generator::NoGuess { attempts: 5 }.generate(
+            &mut StdRng::seed_from_u64(7),
+            &config,
+            2,
+            &start,
+            &[start],
+        );
+
+        assert!(matches!(result, Err(BoardError::GenerationFailed(5))));
+    }
+}
@@ -1,5 +1,19 @@
+pub mod autoplay;
+pub mod boardtext;
+pub mod difficulty;
+pub mod engine;
 pub mod gameloop;
+pub mod generator;
 pub mod mineboard;
 pub mod utils;
 pub mod testfn;
-pub mod menu;
\ No newline at end of file
+pub mod menu;
+pub mod replay;
+pub mod save;
+pub mod scores;
+pub mod screens;
+pub mod settings;
+pub mod setup;
+pub mod solver;
+pub mod stats;
+pub mod viewer;
\ No newline at end of file
@@ -1,7 +1,133 @@
-use terminal_minesweeper::gameloop;
-use terminal_minesweeper::mineboard;
-fn main()  {
-    gameloop::test1();
+use std::path::Path;
+use terminal_minesweeper::autoplay::Benchmark;
+use terminal_minesweeper::difficulty::Difficulty;
+use terminal_minesweeper::gameloop::{self, Game, GameOptions, GameState};
+use terminal_minesweeper::screens;
+use terminal_minesweeper::settings::{self, Settings};
+use terminal_minesweeper::setup;
+use terminal_minesweeper::viewer;
+
+const USAGE: &str = "usage: terminal_minesweeper [--seed <SEED>] [--practice] \
+[--generator <random|no-guess:ATTEMPTS>] [--first-click <safe|opening>] [--benchmark <GAMES>] \
+[--replay <FILE|last>]";
+
+fn main() {
+    let mut options = GameOptions::default();
+    let mut benchmark = None;
+    let mut replay: Option<String> = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--practice" => options.practice = true,
+            "--seed" => options.seed = Some(value(&mut args)),
+            "--generator" => options.generator = value(&mut args),
+            "--first-click" => options.first_click = value(&mut args),
+            "--benchmark" => benchmark = Some(value(&mut args)),
+            "--replay" => replay = Some(value(&mut args)),
+            _ => {
+                eprintln!("unknown argument: {}\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(games) = benchmark {
+        run_benchmark(games, &options);
+        return;
+    }
+
+    let mut notice = None;
+    let mut settings = match settings::default_path().map(|path| settings::load(&path)) {
+        Some(Ok((settings, warnings))) => {
+            if !warnings.is_empty() {
+                let warnings: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+                notice = Some(warnings.join("\n\r"));
+            }
+            settings
+        }
+        Some(Err(e)) => {
+            notice = Some(format!("could not read the settings: {}", e));
+            Settings::default()
+        }
+        None => Settings::default(),
+    };
+
+    if let Some(replay) = replay {
+        let error = match replay.as_str() {
+            "last" => viewer::watch_last(&settings),
+            path => viewer::watch(Path::new(path), &settings)
+                .err()
+                .map(|e| format!("could not watch {}: {}", path, e)),
+        };
+        if let Some(error) = error {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // A seed asks for a new board, so a saved game waits for the next run.
+    if options.seed.is_none() {
+        match gameloop::resume(&settings) {
+            Ok(Some(mut game)) => play(&mut game),
+            Ok(None) => (),
+            Err(e) => {
+                notice = Some(match notice {
+                    Some(notice) => format!("{}\n\r{}", notice, e),
+                    None => e,
+                })
+            }
+        }
+    }
+    while let Some(difficulty) =
+        setup::run_setup(&mut settings, &mut options, notice.take().as_deref())
+    {
+        options.question_marks = settings.question_marks;
+        let mut game = Game::new(
+            settings.board_config(&difficulty),
+            difficulty.landmines(),
+            options.clone(),
+        )
+        .unwrap()
+        .with_keys(settings.keys.clone());
+        // The seed is for the first game, later ones get new boards.
+        options.seed = None;
+        play(&mut game);
+    }
 }
 
+fn play(game: &mut Game) {
+    game.game_loop();
+    // Practice games can take back moves, so they do not count.
+    if game.state() == &GameState::Win && !game.engine().is_practice() {
+        screens::record_win(game.engine());
+    }
+}
+
+/// Parses the value that follows an option, or exits with the usage.
+fn value<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>) -> T {
+    match args.next().map(|s| s.parse()) {
+        Some(Ok(value)) => value,
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    }
+}
 
+/// Lets the automatic player play `games` seeded games on every preset and
+/// prints how it did.
+fn run_benchmark(games: usize, options: &GameOptions) {
+    println!(
+        "generator: {}  first click: {}",
+        options.generator, options.first_click
+    );
+    for difficulty in &Difficulty::PRESETS {
+        match Benchmark::run(difficulty, games, options) {
+            Ok(result) => println!("{}", result),
+            Err(e) => {
+                eprintln!("{}: {}", difficulty.name(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -1,34 +1,427 @@
 #![cfg(feature = "bracketed-paste")]
 use crossterm::cursor;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, ModifierKeyCode};
-use crossterm::style::{Color, Print};
+use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::execute;
+use crossterm::style::Print;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
-use crossterm::{
-    event::{
-        read, DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
-        EnableFocusChange, EnableMouseCapture, Event,
-    },
-    execute,
-};
-use items::{Lable, MenuItem};
+use items::{FormattedString, MenuItem, MenuObject, MenuType, MenuValue, Value};
 use std::collections::HashMap;
-use std::default;
 use std::io::stdout;
 
-mod items;
+pub mod items;
+
+const TAB_WIDTH: usize = 8;
+const FOCUS_MARKER: &str = "> ";
+
+/// Values of a closed menu, keyed by the text of each item.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MenuResult {
+    /// The button that closed the menu, if it was not closed with Enter on
+    /// another item.
+    pub pressed: Option<String>,
+    values: HashMap<String, MenuValue>,
+}
+
+impl MenuResult {
+    pub fn get(&self, text: &str) -> Option<&MenuValue> {
+        self.values.get(text)
+    }
+
+    pub fn get_choice(&self, text: &str) -> Option<&str> {
+        match self.values.get(text) {
+            Some(MenuValue::Choice(choice)) => choice.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_choices(&self, text: &str) -> Option<&[String]> {
+        match self.values.get(text) {
+            Some(MenuValue::Choices(choices)) => Some(choices),
+            _ => None,
+        }
+    }
+
+    pub fn get_text(&self, text: &str) -> Option<&str> {
+        match self.values.get(text) {
+            Some(MenuValue::Text(input)) => Some(input),
+            _ => None,
+        }
+    }
+
+    /// The value of an integer text box.
+    pub fn get_integer(&self, text: &str) -> Option<i64> {
+        self.get_text(text)?.trim().parse().ok()
+    }
+
+    pub fn is_pressed(&self, text: &str) -> bool {
+        self.pressed.as_deref() == Some(text)
+    }
+}
+
+/// Outcome of one key press.
+enum KeyResult {
+    Continue,
+    Confirm,
+    Press(String),
+    Cancel,
+}
 
 pub struct Menu {
     items: Vec<MenuItem>,
+    focus: usize,
+    option_focus: usize,
+    message: Option<String>,
 }
 
 impl Menu {
     pub fn new(items: Vec<MenuItem>) -> Self {
-        Self { items }
+        let focus = items
+            .iter()
+            .position(|item| item.get_type() != &MenuType::Lable)
+            .unwrap_or(0);
+        let mut menu = Self {
+            items,
+            focus,
+            option_focus: 0,
+            message: None,
+        };
+        menu.reset_option_focus();
+        menu
+    }
+
+    pub fn items(&self) -> &[MenuItem] {
+        &self.items
+    }
+
+    /// The item shown with `text`.
+    pub fn get(&self, text: &str) -> Option<&MenuItem> {
+        self.items.iter().find(|item| item.get_text() == text)
+    }
+
+    pub fn get_mut(&mut self, text: &str) -> Option<&mut MenuItem> {
+        self.items.iter_mut().find(|item| item.get_text() == text)
+    }
+
+    /// Shows `message` below the items until it is replaced.
+    pub fn set_message(&mut self, message: &str) {
+        self.message = Some(message.to_string());
+    }
+
+    pub fn clear_message(&mut self) {
+        self.message = None;
+    }
+
+    /// Values of every item that holds one.
+    pub fn result(&self) -> MenuResult {
+        let values = self
+            .items
+            .iter()
+            .filter_map(|item| Some((item.get_text().to_string(), item.value()?)))
+            .collect();
+        MenuResult {
+            pressed: None,
+            values,
+        }
+    }
+
+    /// Lets the player fill in the menu. Returns the values once it is
+    /// confirmed with Enter or a button, and `None` when it is left with
+    /// Esc.
+    pub fn run(&mut self) -> Option<MenuResult> {
+        enable_raw_mode().unwrap();
+        let mut stdout = stdout();
+
+        let result = loop {
+            self.rander();
+            let key_event = match read().unwrap() {
+                Event::Key(key_event) => key_event,
+                _ => continue,
+            };
+            match self.process_key(key_event) {
+                KeyResult::Continue => (),
+                KeyResult::Confirm => break Some(self.result()),
+                KeyResult::Press(button) => {
+                    let mut result = self.result();
+                    result.pressed = Some(button);
+                    break Some(result);
+                }
+                KeyResult::Cancel => break None,
+            }
+        };
+
+        execute!(stdout, Clear(ClearType::All), cursor::Show).unwrap();
+        disable_raw_mode().unwrap();
+        result
+    }
+
+    fn rander(&self) {
+        let mut stdout = stdout();
+        execute!(
+            stdout,
+            Clear(ClearType::All),
+            cursor::MoveTo(0, 0),
+            Print(self)
+        )
+        .unwrap();
+
+        let offset = self
+            .items
+            .get(self.focus)
+            .and_then(|item| item.cursor_offset(self.option_focus));
+        match offset {
+            Some(offset) => execute!(
+                stdout,
+                cursor::MoveTo((self.value_column() + offset) as u16, self.focus as u16),
+                cursor::Show
+            ),
+            None => execute!(stdout, cursor::Hide),
+        }
+        .unwrap();
+    }
+
+    fn process_key(&mut self, key_event: KeyEvent) -> KeyResult {
+        if key_event.kind != KeyEventKind::Press {
+            return KeyResult::Continue;
+        }
+        let Some(item) = self.items.get_mut(self.focus) else {
+            // An empty menu can only be left.
+            return match key_event.code {
+                KeyCode::Esc => KeyResult::Cancel,
+                _ => KeyResult::Continue,
+            };
+        };
+
+        match (item, key_event.code) {
+            (_, KeyCode::Esc) => return KeyResult::Cancel,
+            (_, KeyCode::Up | KeyCode::BackTab) => self.move_focus(false),
+            (_, KeyCode::Down | KeyCode::Tab) => self.move_focus(true),
+            (MenuItem::Button(button), KeyCode::Enter | KeyCode::Char(' ')) => {
+                if let Some(name) = button.get_options().first().cloned() {
+                    button.change(&name, true);
+                }
+                return KeyResult::Press(button.get_text().to_string());
+            }
+            (_, KeyCode::Enter) => match self.check() {
+                Ok(()) => return KeyResult::Confirm,
+                Err(message) => self.set_message(&message),
+            },
+            (MenuItem::RadioButton(_), KeyCode::Left) => self.change_choice(false),
+            (MenuItem::RadioButton(_), KeyCode::Right) => self.change_choice(true),
+            (MenuItem::Selection(_), KeyCode::Left) => self.move_option_focus(false),
+            (MenuItem::Selection(_), KeyCode::Right) => self.move_option_focus(true),
+            (MenuItem::Selection(selection), KeyCode::Char(' ')) => {
+                let Some(name) = selection.get_options().get(self.option_focus).cloned() else {
+                    return KeyResult::Continue;
+                };
+                let selected = *selection.get(&name).unwrap();
+                if selection.change(&name, !selected).is_none() {
+                    let message = if selected {
+                        format!("Keep at least {} selected", selection.min())
+                    } else {
+                        format!("Select at most {}", selection.max())
+                    };
+                    self.set_message(&message);
+                }
+            }
+            (MenuItem::TextBox(text_box), code) => match code {
+                KeyCode::Left => text_box.move_cursor(-1),
+                KeyCode::Right => text_box.move_cursor(1),
+                KeyCode::Home => text_box.move_cursor(isize::MIN),
+                KeyCode::End => text_box.move_cursor(isize::MAX),
+                KeyCode::Backspace => text_box.backspace(),
+                KeyCode::Delete => text_box.delete(),
+                KeyCode::Char(c) => {
+                    if let Err(message) = text_box.insert(c) {
+                        self.message = Some(message);
+                    }
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+        KeyResult::Continue
+    }
+
+    fn move_focus(&mut self, forward: bool) {
+        let count = self.items.len();
+        let mut focus = self.focus;
+        for _ in 0..count {
+            focus = if forward {
+                (focus + 1) % count
+            } else {
+                (focus + count - 1) % count
+            };
+            if self.items[focus].get_type() != &MenuType::Lable {
+                self.focus = focus;
+                self.reset_option_focus();
+                return;
+            }
+        }
+    }
+
+    /// Puts the option focus on the chosen option of a radio button, and
+    /// on the first option of anything else.
+    fn reset_option_focus(&mut self) {
+        self.option_focus = match self.items.get(self.focus) {
+            Some(MenuItem::RadioButton(radio)) => radio
+                .get_options()
+                .iter()
+                .position(|name| radio.get(name) == Some(&true))
+                .unwrap_or(0),
+            _ => 0,
+        };
+    }
+
+    fn move_option_focus(&mut self, forward: bool) {
+        let count = match self.items.get(self.focus) {
+            Some(MenuItem::Selection(selection)) => selection.get_options().len(),
+            Some(MenuItem::RadioButton(radio)) => radio.get_options().len(),
+            _ => return,
+        };
+        if count == 0 {
+            return;
+        }
+        self.option_focus = if forward {
+            (self.option_focus + 1) % count
+        } else {
+            (self.option_focus + count - 1) % count
+        };
+    }
+
+    /// Moves the choice of a focused radio button to the next or previous
+    /// option.
+    fn change_choice(&mut self, forward: bool) {
+        self.move_option_focus(forward);
+        let option_focus = self.option_focus;
+        if let Some(MenuItem::RadioButton(radio)) = self.items.get_mut(self.focus) {
+            if let Some(name) = radio.get_options().get(option_focus).cloned() {
+                radio.change(&name, true);
+            }
+        }
+    }
+
+    /// The first problem with the values of the items, if any.
+    fn check(&self) -> Result<(), String> {
+        self.items.iter().try_for_each(MenuItem::check)
+    }
+
+    /// Column the values of every item line up at, after the longest text.
+    fn value_column(&self) -> usize {
+        let longest = self.items.iter().map(Self::text_width).max().unwrap_or(0);
+        (longest / TAB_WIDTH + 1) * TAB_WIDTH
+    }
+
+    fn text_width(item: &MenuItem) -> usize {
+        FOCUS_MARKER.len() + item.get_text().chars().count() + 1
+    }
+
+    fn number_of_tab(&self, item: &MenuItem) -> u32 {
+        let width = Self::text_width(item);
+        ((self.value_column() - width / TAB_WIDTH * TAB_WIDTH) / TAB_WIDTH) as u32
     }
 }
 
 impl std::fmt::Display for Menu {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, item) in self.items.iter().enumerate() {
+            let marker = if i == self.focus && item.get_type() != &MenuType::Lable {
+                FOCUS_MARKER
+            } else {
+                "  "
+            };
+            write!(f, "{}{}\n\r", marker, item.format(self.number_of_tab(item)))?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, "\n\r{}\n\r", message)?;
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::items::{Button, Lable, RadioButton, Selection, TextBox};
+    use super::*;
+
+    fn press(menu: &mut Menu, code: KeyCode) -> KeyResult {
+        menu.process_key(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn keyboard_navigation() {
+        let options = vec!["a".to_string(), "b".to_string()];
+        let mut radio = RadioButton::new("Radio", &options);
+        radio.change("a", true);
+        let mut menu = Menu::new(vec![
+            MenuItem::Lable(Lable::new("Title")),
+            MenuItem::RadioButton(radio),
+            MenuItem::Selection(Selection::new("Selection", &options)),
+            MenuItem::TextBox(TextBox::new("Name", "Name", "ab")),
+            MenuItem::Button(Button::new("Go", "Go")),
+        ]);
+        assert_eq!(menu.focus, 1);
+
+        press(&mut menu, KeyCode::Right);
+        press(&mut menu, KeyCode::Down);
+        press(&mut menu, KeyCode::Right);
+        press(&mut menu, KeyCode::Char(' '));
+        press(&mut menu, KeyCode::Down);
+        press(&mut menu, KeyCode::Char('x'));
+        press(&mut menu, KeyCode::Char('y'));
+        press(&mut menu, KeyCode::Char('z'));
+        press(&mut menu, KeyCode::Home);
+        press(&mut menu, KeyCode::Delete);
+        press(&mut menu, KeyCode::End);
+        press(&mut menu, KeyCode::Left);
+        press(&mut menu, KeyCode::Backspace);
+        press(&mut menu, KeyCode::Down);
+
+        let result = match press(&mut menu, KeyCode::Enter) {
+            KeyResult::Press(button) => button,
+            _ => panic!("the button was not pressed"),
+        };
+        assert_eq!(result, "Go");
+        let values = menu.result();
+        assert_eq!(values.get_choice("Radio"), Some("b"));
+        assert_eq!(
+            values.get_choices("Selection"),
+            Some(&["b".to_string()][..])
+        );
+        assert_eq!(values.get_text("Name"), Some("z"));
+
+        press(&mut menu, KeyCode::Down);
+        assert_eq!(menu.focus, 1);
+        assert!(matches!(press(&mut menu, KeyCode::Esc), KeyResult::Cancel));
+    }
+
+    #[test]
+    fn empty_menu_ignores_keys() {
+        let mut menu = Menu::new(Vec::new());
+        for code in [
+            KeyCode::Down,
+            KeyCode::Up,
+            KeyCode::Enter,
+            KeyCode::Char(' '),
+        ] {
+            assert!(matches!(press(&mut menu, code), KeyResult::Continue));
+        }
+        assert!(matches!(press(&mut menu, KeyCode::Esc), KeyResult::Cancel));
+    }
+
+    #[test]
+    fn items_without_options_ignore_keys() {
+        let mut menu = Menu::new(vec![
+            MenuItem::RadioButton(RadioButton::new("Radio", &[])),
+            MenuItem::Selection(Selection::new("Selection", &[])),
+        ]);
+        for code in [KeyCode::Left, KeyCode::Right, KeyCode::Char(' ')] {
+            press(&mut menu, code);
+        }
+        press(&mut menu, KeyCode::Down);
+        for code in [KeyCode::Left, KeyCode::Right, KeyCode::Char(' ')] {
+            press(&mut menu, code);
+        }
+        assert_eq!(menu.focus, 1);
+        assert_eq!(menu.result().get_choices("Selection"), Some(&[][..]));
+    }
+}
@@ -1,14 +1,43 @@
-use core::num;
-use std::{collections::HashMap, default};
+use std::collections::HashMap;
 
 fn get_tabs(number: u32) -> String {
     let mut tab = String::new();
     for _ in 0..number {
         tab.push('\t');
-    };
+    }
     tab
 }
 
+/// Lists the options, bracketing the selected ones. Options that are not
+/// next to a selected one are separated by commas.
+fn format_options(options: &[String], is_selected: impl Fn(&str) -> bool) -> String {
+    layout_options(options, is_selected).0
+}
+
+/// The formatted options together with the column each option name starts
+/// at.
+fn layout_options(options: &[String], is_selected: impl Fn(&str) -> bool) -> (String, Vec<usize>) {
+    let mut output = String::new();
+    let mut offsets = vec![];
+    let mut previous_selected = None;
+    for name in options {
+        let selected = is_selected(name);
+        match previous_selected {
+            Some(false) if !selected => output += ", ",
+            Some(_) => output += " ",
+            None => (),
+        }
+        offsets.push(output.chars().count() + 2);
+        if selected {
+            output += &format!("[ {} ]", name);
+        } else {
+            output += &format!("  {}  ", name);
+        }
+        previous_selected = Some(selected);
+    }
+    (output, offsets)
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum MenuType {
     Lable,
@@ -27,6 +56,97 @@ pub enum MenuItem {
     TextBox(TextBox),
 }
 
+impl MenuObject for MenuItem {
+    fn get_text(&self) -> &str {
+        match self {
+            MenuItem::Lable(item) => item.get_text(),
+            MenuItem::Button(item) => item.get_text(),
+            MenuItem::RadioButton(item) => item.get_text(),
+            MenuItem::Selection(item) => item.get_text(),
+            MenuItem::TextBox(item) => item.get_text(),
+        }
+    }
+
+    fn get_type(&self) -> &MenuType {
+        match self {
+            MenuItem::Lable(item) => item.get_type(),
+            MenuItem::Button(item) => item.get_type(),
+            MenuItem::RadioButton(item) => item.get_type(),
+            MenuItem::Selection(item) => item.get_type(),
+            MenuItem::TextBox(item) => item.get_type(),
+        }
+    }
+}
+
+/// The value of a menu item once the menu is closed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MenuValue {
+    Pressed(bool),
+    Choice(Option<String>),
+    Choices(Vec<String>),
+    Text(String),
+}
+
+impl MenuItem {
+    pub fn value(&self) -> Option<MenuValue> {
+        match self {
+            MenuItem::Lable(_) => None,
+            MenuItem::Button(item) => Some(MenuValue::Pressed(
+                item.get_options()
+                    .iter()
+                    .any(|name| item.get(name) == Some(&true)),
+            )),
+            MenuItem::RadioButton(item) => {
+                Some(MenuValue::Choice(item.selected().map(str::to_string)))
+            }
+            MenuItem::Selection(item) => Some(MenuValue::Choices(
+                item.selected().into_iter().map(str::to_string).collect(),
+            )),
+            MenuItem::TextBox(item) => Some(MenuValue::Text(item.value().to_string())),
+        }
+    }
+
+    /// Checks the value before the menu is confirmed.
+    pub fn check(&self) -> Result<(), String> {
+        match self {
+            MenuItem::Selection(item) => item.check(),
+            MenuItem::TextBox(item) => item.check(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Column, counted from the start of the value, where the cursor goes
+    /// when `option` of this item is focused.
+    pub fn cursor_offset(&self, option: usize) -> Option<usize> {
+        match self {
+            MenuItem::RadioButton(item) => {
+                let (_, offsets) =
+                    layout_options(item.get_options(), |name| *item.get(name).unwrap());
+                offsets.get(option).copied()
+            }
+            MenuItem::Selection(item) => {
+                let (_, offsets) =
+                    layout_options(item.get_options(), |name| *item.get(name).unwrap());
+                offsets.get(option).copied()
+            }
+            MenuItem::TextBox(item) => Some(item.get_cursor()),
+            _ => None,
+        }
+    }
+}
+
+impl FormattedString for MenuItem {
+    fn format(&self, number_of_tab: u32) -> String {
+        match self {
+            MenuItem::Lable(item) => item.format(number_of_tab),
+            MenuItem::Button(item) => item.format(number_of_tab),
+            MenuItem::RadioButton(item) => item.format(number_of_tab),
+            MenuItem::Selection(item) => item.format(number_of_tab),
+            MenuItem::TextBox(item) => item.format(number_of_tab),
+        }
+    }
+}
+
 pub trait MenuObject {
     fn get_text(&self) -> &str;
     fn get_type(&self) -> &MenuType;
@@ -127,19 +247,12 @@ impl MenuObject for Button {
 
 impl FormattedString for Button {
     fn format(&self, number_of_tab: u32) -> String {
-        let mut output = String::new();
-
-        output += self.get_text();
-        output += &get_tabs(number_of_tab);
-
-        for name in self.get_options() {
-            if *self.get(name).unwrap() {
-                output += &format!("[{}] ", name);
-            } else {
-                output += &format!(" {}  ", name);
-            }
-        }
-        output
+        format!(
+            "{}:{}{}",
+            self.get_text(),
+            get_tabs(number_of_tab),
+            format_options(self.get_options(), |name| *self.get(name).unwrap())
+        )
     }
 }
 
@@ -152,10 +265,11 @@ pub struct RadioButton {
 }
 
 impl RadioButton {
+    /// A radio button with the first option chosen.
     pub fn new(text: &str, options_text: &[String]) -> Self {
         let mut options = HashMap::new();
-        for text in options_text {
-            options.insert(text.clone(), false);
+        for (i, text) in options_text.iter().enumerate() {
+            options.insert(text.clone(), i == 0);
         }
         let options_text = options_text.to_vec();
 
@@ -166,6 +280,21 @@ impl RadioButton {
             menu_type: MenuType::RadioButton,
         }
     }
+
+    /// Chooses `name` instead of the first option. Unknown names are
+    /// ignored.
+    pub fn with_default(mut self, name: &str) -> Self {
+        self.change(name, true);
+        self
+    }
+
+    /// The chosen option. Only `None` when there are no options.
+    pub fn selected(&self) -> Option<&str> {
+        self.options_text
+            .iter()
+            .find(|name| self.options[*name])
+            .map(|name| &name[..])
+    }
 }
 
 impl MenuObject for RadioButton {
@@ -183,14 +312,18 @@ impl Value<bool, bool> for RadioButton {
         self.options.get(name)
     }
 
+    /// Choosing an option clears the others. Clearing an option is ignored,
+    /// since exactly one option is always chosen.
     fn change(&mut self, name: &str, value: bool) -> Option<()> {
-        match self.options.get_mut(name) {
-            Some(v) => {
-                *v = value;
-                Some(())
+        if !self.options.contains_key(name) {
+            return None;
+        }
+        if value {
+            for (option, selected) in self.options.iter_mut() {
+                *selected = option == name;
             }
-            None => None,
         }
+        Some(())
     }
 
     fn get_options(&self) -> &[String] {
@@ -200,19 +333,12 @@ impl Value<bool, bool> for RadioButton {
 
 impl FormattedString for RadioButton {
     fn format(&self, number_of_tab: u32) -> String {
-        let mut output = String::new();
-
-        output += self.get_text();
-        output += &get_tabs(number_of_tab);
-
-        for name in self.get_options() {
-            if *self.get(name).unwrap() {
-                output += &format!("[{}] ", name);
-            } else {
-                output += &format!(" {}  ", name);
-            }
-        }
-        output
+        format!(
+            "{}:{}{}",
+            self.get_text(),
+            get_tabs(number_of_tab),
+            format_options(self.get_options(), |name| *self.get(name).unwrap())
+        )
     }
 }
 
@@ -221,10 +347,14 @@ pub struct Selection {
     text: String,
     options_text: Vec<String>,
     options: HashMap<String, bool>,
+    min: usize,
+    max: usize,
     menu_type: MenuType,
 }
 
 impl Selection {
+    /// A selection with nothing selected and no limit on how many options
+    /// may be.
     pub fn new(text: &str, options_text: &[String]) -> Self {
         let mut options = HashMap::new();
         for text in options_text {
@@ -234,11 +364,48 @@ impl Selection {
 
         Self {
             text: text.to_string(),
+            max: options_text.len(),
             options_text,
             options,
+            min: 0,
             menu_type: MenuType::Selection,
         }
     }
+
+    /// Requires between `min` and `max` options to be selected.
+    pub fn with_limits(mut self, min: usize, max: usize) -> Self {
+        self.min = min;
+        self.max = max.max(min);
+        self
+    }
+
+    pub fn min(&self) -> usize {
+        self.min
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// The selected options, in the order they are shown.
+    pub fn selected(&self) -> Vec<&str> {
+        self.options_text
+            .iter()
+            .filter(|name| self.options[*name])
+            .map(|name| &name[..])
+            .collect()
+    }
+
+    /// Checks that enough options are selected.
+    pub fn check(&self) -> Result<(), String> {
+        if self.selected().len() < self.min {
+            return Err(format!(
+                "{}: select at least {} option(s)",
+                self.text, self.min
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl MenuObject for Selection {
@@ -252,18 +419,22 @@ impl MenuObject for Selection {
 }
 
 impl Value<bool, bool> for Selection {
-    fn get(&self, name: &str) -> Option<&bool>{
+    fn get(&self, name: &str) -> Option<&bool> {
         self.options.get(name)
     }
 
+    /// Returns `None` when `name` is not an option or when the change would
+    /// take the number of selected options past the limits.
     fn change(&mut self, name: &str, value: bool) -> Option<()> {
-        match self.options.get_mut(name) {
-            Some(v) => {
-                *v = value;
-                Some(())
+        let current = *self.options.get(name)?;
+        if current != value {
+            let count = self.selected().len();
+            if (value && count >= self.max) || (!value && count <= self.min) {
+                return None;
             }
-            None => None,
         }
+        self.options.insert(name.to_string(), value);
+        Some(())
     }
 
     fn get_options(&self) -> &[String] {
@@ -273,19 +444,12 @@ impl Value<bool, bool> for Selection {
 
 impl FormattedString for Selection {
     fn format(&self, number_of_tab: u32) -> String {
-        let mut output = String::new();
-
-        output += self.get_text();
-        output += &get_tabs(number_of_tab);
-
-        for name in self.get_options() {
-            if *self.get(name).unwrap() {
-                output += &format!("[{}] ", name);
-            } else {
-                output += &format!(" {}  ", name);
-            }
-        }
-        output
+        format!(
+            "{}:{}{}",
+            self.get_text(),
+            get_tabs(number_of_tab),
+            format_options(self.get_options(), |name| *self.get(name).unwrap())
+        )
     }
 }
 
@@ -293,21 +457,197 @@ impl FormattedString for Selection {
 pub struct TextBox {
     text: String,
     input: String,
+    cursor: usize,
     options_text: Vec<String>,
     default_input: String,
+    kind: InputKind,
+    max_length: Option<usize>,
+    filter: Option<CharFilter>,
     menu_type: MenuType,
 }
 
+/// What a text box holds once it is filled in.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum InputKind {
+    #[default]
+    Text,
+    /// A whole number between `min` and `max`, both included.
+    Integer { min: i64, max: i64 },
+}
+
+/// Characters a text box accepts, written like a regex character class
+/// without the brackets: `a-z0-9_` accepts lowercase letters, digits and
+/// underscores. A `-` at the start or end is taken literally.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CharFilter {
+    ranges: Vec<(char, char)>,
+}
+
+impl CharFilter {
+    pub fn new(pattern: &str) -> Self {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut ranges = vec![];
+        let mut i = 0;
+        while i < chars.len() {
+            if i + 2 < chars.len() && chars[i + 1] == '-' {
+                ranges.push((chars[i], chars[i + 2]));
+                i += 3;
+            } else {
+                ranges.push((chars[i], chars[i]));
+                i += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn allows(&self, c: char) -> bool {
+        self.ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&c))
+    }
+}
+
 impl TextBox {
+    /// A free text box showing `default_input` until something is typed.
     pub fn new(text: &str, option_text: &str, default_input: &str) -> Self {
         Self {
             text: text.to_string(),
             input: String::new(),
+            cursor: 0,
             options_text: vec![option_text.to_string()],
             default_input: default_input.to_string(),
+            kind: InputKind::Text,
+            max_length: None,
+            filter: None,
             menu_type: MenuType::TextBox,
         }
     }
+
+    /// Only accepts whole numbers between `min` and `max`.
+    pub fn integer(mut self, min: i64, max: i64) -> Self {
+        self.kind = InputKind::Integer { min, max };
+        let filter = if min < 0 { "0-9-" } else { "0-9" };
+        self.filter = Some(CharFilter::new(filter));
+        self.max_length = Some(min.to_string().len().max(max.to_string().len()));
+        self
+    }
+
+    /// Accepts at most `max_length` characters.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Only accepts characters matching `pattern`, see [`CharFilter`].
+    pub fn filter(mut self, pattern: &str) -> Self {
+        self.filter = Some(CharFilter::new(pattern));
+        self
+    }
+
+    pub fn kind(&self) -> &InputKind {
+        &self.kind
+    }
+
+    /// The value, falling back to the default when nothing was typed.
+    pub fn value(&self) -> &str {
+        if self.input.is_empty() {
+            &self.default_input
+        } else {
+            &self.input
+        }
+    }
+
+    /// The value as a number, when it is one.
+    pub fn get_integer(&self) -> Option<i64> {
+        self.value().parse().ok()
+    }
+
+    /// Checks the value against the kind of the text box.
+    pub fn check(&self) -> Result<(), String> {
+        match self.kind {
+            InputKind::Text => Ok(()),
+            InputKind::Integer { min, max } => match self.get_integer() {
+                Some(value) if (min..=max).contains(&value) => Ok(()),
+                Some(_) => Err(format!("{} must be between {} and {}", self.text, min, max)),
+                None => Err(format!(
+                    "{} must be a whole number, not '{}'",
+                    self.text,
+                    self.value()
+                )),
+            },
+        }
+    }
+
+    /// Checks that `input` only holds allowed characters and is not too
+    /// long.
+    fn accepts(&self, input: &str) -> Result<(), String> {
+        if let Some(max_length) = self.max_length {
+            if input.chars().count() > max_length {
+                return Err(format!(
+                    "{} takes at most {} characters",
+                    self.text, max_length
+                ));
+            }
+        }
+        if let Some(filter) = &self.filter {
+            if let Some(c) = input.chars().find(|c| !filter.allows(*c)) {
+                return Err(format!("{} does not take '{}'", self.text, c));
+            }
+        }
+        Ok(())
+    }
+
+    /// What was typed, without falling back to the default.
+    pub fn get_input(&self) -> &str {
+        &self.input
+    }
+
+    /// Position of the cursor, in characters from the start of the input.
+    pub fn get_cursor(&self) -> usize {
+        self.cursor
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.input
+            .char_indices()
+            .nth(cursor)
+            .map(|(i, _)| i)
+            .unwrap_or(self.input.len())
+    }
+
+    /// Types `c` at the cursor, unless the text box does not take it.
+    pub fn insert(&mut self, c: char) -> Result<(), String> {
+        let index = self.byte_index(self.cursor);
+        let mut input = self.input.clone();
+        input.insert(index, c);
+        self.accepts(&input)?;
+        self.input = input;
+        self.cursor += 1;
+        Ok(())
+    }
+
+    /// Deletes the character before the cursor.
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let index = self.byte_index(self.cursor);
+            self.input.remove(index);
+        }
+    }
+
+    /// Deletes the character under the cursor.
+    pub fn delete(&mut self) {
+        if self.cursor < self.input.chars().count() {
+            let index = self.byte_index(self.cursor);
+            self.input.remove(index);
+        }
+    }
+
+    /// Moves the cursor by `step` characters, staying inside the input.
+    pub fn move_cursor(&mut self, step: isize) {
+        let length = self.input.chars().count() as isize;
+        self.cursor = (self.cursor as isize).saturating_add(step).clamp(0, length) as usize;
+    }
 }
 
 impl MenuObject for TextBox {
@@ -323,7 +663,7 @@ impl MenuObject for TextBox {
 impl Value<String, &str> for TextBox {
     fn get(&self, name: &str) -> Option<&String> {
         if name == self.options_text[0] {
-            if self.input.len() == 0 {
+            if self.input.is_empty() {
                 Some(&self.default_input)
             } else {
                 Some(&self.input)
@@ -333,8 +673,14 @@ impl Value<String, &str> for TextBox {
         }
     }
 
+    /// Returns `None` when `name` is not the option of this text box or the
+    /// text box does not take `value`.
     fn change(&mut self, name: &str, value: &str) -> Option<()> {
+        if name != self.options_text[0] || self.accepts(value).is_err() {
+            return None;
+        }
         self.input = value.to_string();
+        self.cursor = self.input.chars().count();
         Some(())
     }
 
@@ -345,12 +691,12 @@ impl Value<String, &str> for TextBox {
 
 impl FormattedString for TextBox {
     fn format(&self, number_of_tab: u32) -> String {
-        let input = if self.input.len() == 0 {
-            &self.default_input
-        } else {
-            &self.input
-        };
-        format!("{}{}{}", self.get_text(), get_tabs(number_of_tab), input)
+        format!(
+            "{}:{}{}",
+            self.get_text(),
+            get_tabs(number_of_tab),
+            self.value()
+        )
     }
 }
 
@@ -383,7 +729,10 @@ mod test {
         button.change(button_text, true).unwrap();
         assert_eq!(button.get(button_text).unwrap(), &true);
 
-        assert_eq!(button.format(NUMBER_OF_TAB), "I am a Button:\t\t\t\t\t[ Enter ]".to_string())
+        assert_eq!(
+            button.format(NUMBER_OF_TAB),
+            "I am a Button:\t\t\t\t\t[ Enter ]".to_string()
+        )
     }
 
     #[test]
@@ -395,7 +744,10 @@ mod test {
         assert_eq!(selection.get_type(), &MenuType::Selection);
         assert_eq!(selection.get_text(), text);
 
-        assert_eq!(selection.format(NUMBER_OF_TAB), "I am a Selection:\t\t\t\t\t  a  ,   b  ,   c  ");
+        assert_eq!(
+            selection.format(NUMBER_OF_TAB),
+            "I am a Selection:\t\t\t\t\t  a  ,   b  ,   c  "
+        );
 
         assert_eq!(selection.get("a"), Some(&false));
         assert_eq!(selection.get("b"), Some(&false));
@@ -409,8 +761,10 @@ mod test {
         assert_eq!(selection.get("b"), Some(&true));
         assert_eq!(selection.get("c"), Some(&true));
 
-        assert_eq!(selection.format(NUMBER_OF_TAB), "I am a Selection:\t\t\t\t\t[ a ] [ b ] [ c ]");
-
+        assert_eq!(
+            selection.format(NUMBER_OF_TAB),
+            "I am a Selection:\t\t\t\t\t[ a ] [ b ] [ c ]"
+        );
 
         assert_eq!(selection.get("d"), None);
     }
@@ -424,7 +778,7 @@ mod test {
         assert_eq!(radio.get_type(), &MenuType::RadioButton);
         assert_eq!(radio.get_text(), text);
 
-        assert_eq!(radio.get("a"), Some(&false));
+        assert_eq!(radio.get("a"), Some(&true));
         assert_eq!(radio.get("b"), Some(&false));
         assert_eq!(radio.get("c"), Some(&false));
 
@@ -432,11 +786,36 @@ mod test {
         radio.change("b", true).unwrap();
         radio.change("c", true).unwrap();
 
-        assert_eq!(radio.get("a"), Some(&true));
-        assert_eq!(radio.get("b"), Some(&true));
+        assert_eq!(radio.get("a"), Some(&false));
+        assert_eq!(radio.get("b"), Some(&false));
         assert_eq!(radio.get("c"), Some(&true));
+        assert_eq!(radio.selected(), Some("c"));
+
+        radio.change("c", false).unwrap();
+        assert_eq!(radio.selected(), Some("c"));
 
         assert_eq!(radio.get("d"), None);
+        assert_eq!(radio.change("d", true), None);
+
+        let radio = RadioButton::new(text, &options_text).with_default("b");
+        assert_eq!(radio.selected(), Some("b"));
+    }
+
+    #[test]
+    fn selection_limits() {
+        let options_text = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let mut selection = Selection::new("Selection", &options_text).with_limits(1, 2);
+        assert!(selection.check().is_err());
+
+        selection.change("c", true).unwrap();
+        selection.change("a", true).unwrap();
+        assert_eq!(selection.change("b", true), None);
+        assert_eq!(selection.selected(), vec!["a", "c"]);
+        assert!(selection.check().is_ok());
+
+        selection.change("a", false).unwrap();
+        assert_eq!(selection.change("c", false), None);
+        assert_eq!(selection.selected(), vec!["c"]);
     }
 
     #[test]
@@ -444,14 +823,91 @@ mod test {
         let text = "I am a TextBox";
         let options_text = "test1";
         let default_text = "shamokwok";
-        
-        let mut text_box = TextBox::new(text, &options_text, default_text);
+
+        let mut text_box = TextBox::new(text, options_text, default_text);
 
         assert_eq!(text_box.get_type(), &MenuType::TextBox);
         assert_eq!(text_box.get(options_text), Some(&default_text.to_string()));
 
-        text_box.change(&default_text, "kiana");
+        assert_eq!(text_box.change(default_text, "kiana"), None);
+        assert_eq!(text_box.get(options_text), Some(&default_text.to_string()));
+
+        text_box.change(options_text, "kiana").unwrap();
         assert_eq!(text_box.get(options_text), Some(&"kiana".to_string()));
+    }
+
+    #[test]
+    fn integer_text_box() {
+        let mut text_box = TextBox::new("Width", "width", "30").integer(1, 200);
+        assert_eq!(text_box.get_integer(), Some(30));
+        assert!(text_box.check().is_ok());
+
+        assert!(text_box.insert('x').is_err());
+        text_box.insert('0').unwrap();
+        assert!(text_box.check().is_err());
+        text_box.insert('5').unwrap();
+        text_box.insert('0').unwrap();
+        assert!(text_box.insert('0').is_err());
+        assert_eq!(text_box.get_input(), "050");
+        assert_eq!(text_box.get_integer(), Some(50));
+        assert!(text_box.check().is_ok());
+
+        assert_eq!(text_box.change("width", "1000"), None);
+        text_box.change("width", "201").unwrap();
+        assert!(text_box.check().is_err());
+    }
+
+    #[test]
+    fn filtered_text_box() {
+        let mut text_box = TextBox::new("Name", "name", "")
+            .max_length(4)
+            .filter("a-z_-");
+        for c in "ab_-".chars() {
+            text_box.insert(c).unwrap();
+        }
+        assert!(text_box.insert('c').is_err());
+        text_box.backspace();
+        assert!(text_box.insert('C').is_err());
+        assert!(text_box.insert('1').is_err());
+        assert_eq!(text_box.get_input(), "ab_");
+        assert_eq!(text_box.change("name", "a b"), None);
+    }
+
+    #[test]
+    fn text_box_editing() {
+        let mut text_box = TextBox::new("Name", "name", "");
+        for c in "kana".chars() {
+            text_box.insert(c).unwrap();
+        }
+        text_box.move_cursor(-3);
+        text_box.insert('i').unwrap();
+        assert_eq!(text_box.get_input(), "kiana");
+        assert_eq!(text_box.get_cursor(), 2);
 
+        text_box.backspace();
+        text_box.delete();
+        assert_eq!(text_box.get_input(), "kna");
+        text_box.move_cursor(10);
+        assert_eq!(text_box.get_cursor(), 3);
+    }
+
+    #[test]
+    fn menu_item_values() {
+        let options_text = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let mut selection = Selection::new("Selection", &options_text);
+        selection.change("a", true).unwrap();
+        selection.change("c", true).unwrap();
+        let selection = MenuItem::Selection(selection);
+
+        assert_eq!(
+            selection.value(),
+            Some(MenuValue::Choices(vec!["a".to_string(), "c".to_string()]))
+        );
+        assert_eq!(selection.cursor_offset(1), Some(8));
+        assert_eq!(MenuItem::Lable(Lable::new("Lable")).value(), None);
+        assert_eq!(
+            MenuItem::TextBox(TextBox::new("Name", "name", "default")).value(),
+            Some(MenuValue::Text("default".to_string()))
+        );
     }
 }
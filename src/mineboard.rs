@@ -1,6 +1,5 @@
 use crossterm::execute;
-use crossterm::style::{Color, Print};
-use crossterm::terminal::{Clear, ClearType};
+use crossterm::style::{Color, Print, Stylize};
 use std::error::Error;
 use std::fmt;
 
@@ -13,6 +12,65 @@ pub enum ItemType {
     Number(i32),
 }
 
+/// Cells opened by a single action on the board.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClickResult {
+    pub opened: Vec<Coordinates>,
+    pub landmine: Option<Coordinates>,
+    /// Opened cells that had a question mark, which opening cleared.
+    pub questions: Vec<Coordinates>,
+}
+
+impl ClickResult {
+    pub fn hit_landmine(&self) -> bool {
+        self.landmine.is_some()
+    }
+
+    fn merge(&mut self, other: ClickResult) {
+        self.opened.extend(other.opened);
+        self.questions.extend(other.questions);
+        if self.landmine.is_none() {
+            self.landmine = other.landmine;
+        }
+    }
+}
+
+/// What the player has put on a closed cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mark {
+    None,
+    Flag,
+    /// The player is unsure. Counts as no flag and does not stop a reveal.
+    Question,
+}
+
+/// What is drawn for each kind of cell. Every symbol should take
+/// `char_width` columns of the terminal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbols {
+    pub landmine: String,
+    pub exploded: String,
+    pub flag: String,
+    pub wrong_flag: String,
+    pub question: String,
+    pub closed: String,
+    pub opened: String,
+}
+
+impl Default for Symbols {
+    fn default() -> Self {
+        Self {
+            landmine: "💣".to_string(),
+            exploded: "💥".to_string(),
+            flag: "🚩".to_string(),
+            wrong_flag: "❌".to_string(),
+            question: "❓".to_string(),
+            closed: "██".to_string(),
+            opened: "  ".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BoardConfig {
     pub height: usize,
@@ -27,9 +85,12 @@ pub struct BoardConfig {
     pub border_right: &'static str,
     pub border_left: &'static str,
     pub board_background_colour: Color,
+    pub number_colour: Color,
+    pub symbols: Symbols,
 }
 
 impl BoardConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         height: usize,
         width: usize,
@@ -57,9 +118,26 @@ impl BoardConfig {
             border_right,
             border_left,
             board_background_colour,
+            number_colour: Color::Reset,
+            symbols: Symbols::default(),
         }
     }
 
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    pub fn with_number_colour(mut self, number_colour: Color) -> Self {
+        self.number_colour = number_colour;
+        self
+    }
+
+    /// Columns and rows taken by the board, its border and the status line.
+    pub fn screen_size(&self) -> (usize, usize) {
+        ((self.width + 2) * self.char_width as usize, self.height + 3)
+    }
+
     pub fn unicode(width: usize, height: usize, board_background_colour: Color) -> Self {
         BoardConfig::new(
             height,
@@ -78,9 +156,22 @@ impl BoardConfig {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BoardError {
     CoordinatesOutOffRange,
+    TooManyLandmines,
+    GenerationFailed(usize),
+    EmptyBoard,
+    UnevenRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidCell {
+        line: usize,
+        column: usize,
+        found: char,
+    },
 }
 
 impl Error for BoardError {}
@@ -89,6 +180,31 @@ impl fmt::Display for BoardError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             BoardError::CoordinatesOutOffRange => write!(f, "coordinates out off range"),
+            BoardError::TooManyLandmines => write!(f, "too many landmines for the board"),
+            BoardError::GenerationFailed(attempts) => write!(
+                f,
+                "no board solvable without guessing found in {} attempts",
+                attempts
+            ),
+            BoardError::EmptyBoard => write!(f, "board has no cells"),
+            BoardError::UnevenRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} cells but found {}",
+                line, expected, found
+            ),
+            BoardError::InvalidCell {
+                line,
+                column,
+                found,
+            } => write!(
+                f,
+                "line {}, column {}: invalid cell '{}'",
+                line, column, found
+            ),
         }
     }
 }
@@ -128,7 +244,6 @@ pub struct LandmineField {
     height: usize,
     field: Vec<Vec<bool>>,
     count: usize,
-    symbol: &'static str,
 }
 
 impl LandmineField {
@@ -141,13 +256,11 @@ impl LandmineField {
         let set_values: Vec<(Coordinates, bool)> =
             landmine_positions.iter().map(|pos| (*pos, true)).collect();
         let field = LandmineField::set_field(&set_values, width, height, false)?;
-        let symbol = "💣";
         Ok(LandmineField {
             width,
             height,
             field,
             count,
-            symbol,
         })
     }
 }
@@ -193,7 +306,6 @@ pub struct FlagsField {
     height: usize,
     field: Vec<Vec<bool>>,
     count: usize,
-    symbol: &'static str,
 }
 
 impl FlagsField {
@@ -201,13 +313,11 @@ impl FlagsField {
         let count = 0;
         let nothing = vec![];
         let field = FlagsField::set_field(&nothing, width, height, false)?;
-        let symbol = "🚩";
         Ok(FlagsField {
             width,
             height,
             field,
             count,
-            symbol,
         })
     }
 }
@@ -244,8 +354,10 @@ impl ChangeableField for FlagsField {
             return Err(BoardError::CoordinatesOutOffRange);
         }
 
-        self.count += 1;
-        self.field[pos.y][pos.x] = true;
+        if !self.field[pos.y][pos.x] {
+            self.count += 1;
+            self.field[pos.y][pos.x] = true;
+        }
         Ok(())
     }
 
@@ -254,8 +366,10 @@ impl ChangeableField for FlagsField {
             return Err(BoardError::CoordinatesOutOffRange);
         }
 
-        self.count += 1;
-        self.field[pos.y][pos.x] = false;
+        if self.field[pos.y][pos.x] {
+            self.count -= 1;
+            self.field[pos.y][pos.x] = false;
+        }
         Ok(())
     }
 }
@@ -266,8 +380,6 @@ pub struct OpenedField {
     height: usize,
     field: Vec<Vec<bool>>,
     count: usize,
-    symbol_open: &'static str,
-    symbol_closed: &'static str,
 }
 
 impl OpenedField {
@@ -275,15 +387,11 @@ impl OpenedField {
         let count = 0;
         let nothing: Vec<(Coordinates, bool)> = vec![];
         let field = FlagsField::set_field(&nothing, width, height, false)?;
-        let symbol_open = "  ";
-        let symbol_closed = "██";
         Ok(OpenedField {
             width,
             height,
             field,
             count,
-            symbol_open,
-            symbol_closed,
         })
     }
 }
@@ -320,8 +428,10 @@ impl ChangeableField for OpenedField {
             return Err(BoardError::CoordinatesOutOffRange);
         }
 
-        self.count += 1;
-        self.field[pos.y][pos.x] = true;
+        if !self.field[pos.y][pos.x] {
+            self.count += 1;
+            self.field[pos.y][pos.x] = true;
+        }
         Ok(())
     }
 
@@ -330,8 +440,10 @@ impl ChangeableField for OpenedField {
             return Err(BoardError::CoordinatesOutOffRange);
         }
 
-        self.count += 1;
-        self.field[pos.y][pos.x] = false;
+        if self.field[pos.y][pos.x] {
+            self.count -= 1;
+            self.field[pos.y][pos.x] = false;
+        }
         Ok(())
     }
 }
@@ -364,69 +476,27 @@ impl NumberField {
         landmines: &LandmineField,
     ) -> Result<Vec<Vec<i32>>, BoardError> {
         let mut set_values = vec![];
-        for i in 0..width as usize {
-            for j in 0..height as usize {
+        for i in 0..width {
+            for j in 0..height {
+                let pos = Coordinates { x: i, y: j };
                 let mut count = 0;
-                if i >= 1 && j >= 1 && i - 1 < width && j - 1 < height {
-                    if landmines.get(&Coordinates { x: i - 1, y: j - 1 })? {
-                        count += 1;
-                    }
-                }
-
-                if i >= 1 && i - 1 < width {
-                    if landmines.get(&Coordinates { x: i - 1, y: j })? {
-                        count += 1;
-                    }
-                }
-
-                if i >= 1 && i - 1 < width && j + 1 < height {
-                    if landmines.get(&Coordinates { x: i - 1, y: j + 1 })? {
-                        count += 1;
-                    }
-                }
-
-                if j >= 1 && j - 1 < height {
-                    if landmines.get(&Coordinates { x: i, y: j - 1 })? {
-                        count += 1;
-                    }
-                }
-
-                if j + 1 < height {
-                    if landmines.get(&Coordinates { x: i, y: j + 1 })? {
-                        count += 1;
-                    }
-                }
-
-                if i + 1 < width && j >= 1 && j - 1 < height {
-                    if landmines.get(&Coordinates { x: i + 1, y: j - 1 })? {
-                        count += 1;
-                    }
-                }
-
-                if i + 1 < width {
-                    if landmines.get(&Coordinates { x: i + 1, y: j })? {
+                for neighbour in pos.neighbours(width, height) {
+                    if landmines.get(&neighbour)? {
                         count += 1;
                     }
                 }
-
-                if i + 1 < width && j + 1 < height {
-                    if landmines.get(&Coordinates { x: i + 1, y: j + 1 })? {
-                        count += 1;
-                    }
-                }
-
-                set_values.push((Coordinates { x: i, y: j }, count));
+                set_values.push((pos, count));
             }
         }
         let field = NumberField::set_field(&set_values, width, height, 0)?;
         Ok(field)
     }
 
-    fn get_count(field: &Vec<Vec<i32>>) -> usize {
+    fn get_count(field: &[Vec<i32>]) -> usize {
         let mut count: usize = 0;
         for row in field {
             for n in row {
-                if *n > 1 {
+                if *n > 0 {
                     count += 1;
                 }
             }
@@ -466,18 +536,22 @@ pub struct Board {
     config: BoardConfig,
     landmines: LandmineField,
     flags: FlagsField,
+    questions: FlagsField,
     opened: OpenedField,
     numbers: NumberField,
     framebuffer: Vec<Vec<String>>,
+    revealed: bool,
+    exploded: Option<Coordinates>,
+    highlight: Option<Coordinates>,
+    /// Chance of a landmine in every cell, row by row.
+    heatmap: Option<Vec<Vec<f64>>>,
 }
 
 impl Board {
-    pub fn new<'b>(
-        config: BoardConfig,
-        landmine_pos: &'b [Coordinates],
-    ) -> Result<Board, BoardError> {
+    pub fn new(config: BoardConfig, landmine_pos: &[Coordinates]) -> Result<Board, BoardError> {
         let landmines = LandmineField::new(landmine_pos, config.width, config.height)?;
         let flags = FlagsField::new(config.width, config.height)?;
+        let questions = FlagsField::new(config.width, config.height)?;
         let opened = OpenedField::new(config.width, config.height)?;
         let framebuffer = vec![vec![String::new(); config.width + 2]; config.height + 3];
         let numbers = NumberField::new(config.width, config.height, &landmines)?;
@@ -485,67 +559,190 @@ impl Board {
             config,
             landmines,
             flags,
+            questions,
             opened,
             numbers,
             framebuffer,
+            revealed: false,
+            exploded: None,
+            highlight: None,
+            heatmap: None,
         })
     }
 
+    /// Replaces the landmine layout and recalculates the numbers.
+    pub fn set_landmines(&mut self, landmine_pos: &[Coordinates]) -> Result<(), BoardError> {
+        self.landmines = LandmineField::new(landmine_pos, self.config.width, self.config.height)?;
+        self.numbers = NumberField::new(self.config.width, self.config.height, &self.landmines)?;
+        Ok(())
+    }
+
     pub fn landmine_count(&self) -> &usize {
         self.landmines.get_count()
     }
 
+    /// Every cell holding a landmine, row by row.
+    pub fn landmine_positions(&self) -> Result<Vec<Coordinates>, BoardError> {
+        let mut positions = vec![];
+        for y in 0..self.config.height {
+            for x in 0..self.config.width {
+                let pos = Coordinates::new(x, y);
+                if self.landmines.get(&pos)? {
+                    positions.push(pos);
+                }
+            }
+        }
+        Ok(positions)
+    }
+
     pub fn get_config(&self) -> &BoardConfig {
         &self.config
     }
 
     pub fn draw_border(&mut self) {
         // Top
-        self.framebuffer[0][0] = self.config.border_top_left_symbol.to_string();
+        self.framebuffer[0][0] =
+            self.paint(self.config.border_top_left_symbol.to_string(), Color::Reset);
         for i in 1..self.config.width + 1 {
-            self.framebuffer[0][i] = self.config.border_top.to_string();
+            self.framebuffer[0][i] = self.paint(self.config.border_top.to_string(), Color::Reset);
         }
-        self.framebuffer[0][self.config.width + 1] =
-            self.config.border_top_right_symbol.to_string();
+        self.framebuffer[0][self.config.width + 1] = self.paint(
+            self.config.border_top_right_symbol.to_string(),
+            Color::Reset,
+        );
 
         // Side
         for j in 1..self.config.height + 1 {
-            self.framebuffer[j][0] = self.config.border_left.to_string();
-            self.framebuffer[j][self.config.width + 1] = self.config.border_right.to_string();
+            self.framebuffer[j][0] = self.paint(self.config.border_left.to_string(), Color::Reset);
+            self.framebuffer[j][self.config.width + 1] =
+                self.paint(self.config.border_right.to_string(), Color::Reset);
         }
 
         // Bottom
-        self.framebuffer[self.config.height + 1][0] =
-            self.config.border_bottom_left_symbol.to_string();
+        self.framebuffer[self.config.height + 1][0] = self.paint(
+            self.config.border_bottom_left_symbol.to_string(),
+            Color::Reset,
+        );
         for i in 1..self.config.width + 1 {
-            self.framebuffer[self.config.height + 1][i] = self.config.border_bottom.to_string();
+            self.framebuffer[self.config.height + 1][i] =
+                self.paint(self.config.border_bottom.to_string(), Color::Reset);
         }
-        self.framebuffer[self.config.height + 1][self.config.width + 1] =
-            self.config.border_bottom_right_symbol.to_string();
+        self.framebuffer[self.config.height + 1][self.config.width + 1] = self.paint(
+            self.config.border_bottom_right_symbol.to_string(),
+            Color::Reset,
+        );
     }
 
     pub fn draw_field(&mut self) -> Result<(), BoardError> {
         for i in 0..self.config.width {
             for j in 0..self.config.height {
                 let pos = Coordinates::new(i, j);
-                self.framebuffer[j + 1][i + 1] = if self.flags.get(&pos)? {
-                    self.flags.symbol.to_string()
-                } else if self.opened.get(&pos)? {
-                    if self.landmines.get(&pos)? {
-                        self.landmines.symbol.to_string()
-                    } else if self.numbers.get(&pos)? > 0 {
-                        format!("{}{}", self.numbers.get(&pos)?, self.numbers.symbol)
-                    } else {
-                        self.opened.symbol_open.to_string()
-                    }
-                } else {
-                    self.opened.symbol_closed.to_string()
-                }
+                self.framebuffer[j + 1][i + 1] = self.cell_symbol(&pos)?;
             }
         }
         Ok(())
     }
 
+    fn cell_symbol(&self, pos: &Coordinates) -> Result<String, BoardError> {
+        let flagged = self.flags.get(pos)?;
+        let landmine = self.landmines.get(pos)?;
+
+        let symbols = &self.config.symbols;
+        let mut colour = Color::Reset;
+        let symbol = if self.exploded == Some(*pos) {
+            symbols.exploded.clone()
+        } else if self.revealed && flagged && !landmine {
+            symbols.wrong_flag.clone()
+        } else if flagged {
+            symbols.flag.clone()
+        } else if self.opened.get(pos)? || (self.revealed && landmine) {
+            if landmine {
+                symbols.landmine.clone()
+            } else if self.numbers.get(pos)? > 0 {
+                colour = self.config.number_colour;
+                format!("{}{}", self.numbers.get(pos)?, self.numbers.symbol)
+            } else {
+                symbols.opened.clone()
+            }
+        } else if let Some(heatmap) = &self.heatmap {
+            let painted = self.heat_symbol(heatmap[pos.y][pos.x]);
+            return Ok(self.highlighted(pos, painted));
+        } else if self.questions.get(pos)? {
+            symbols.question.clone()
+        } else {
+            symbols.closed.clone()
+        };
+        Ok(self.highlighted(pos, self.paint(symbol, colour)))
+    }
+
+    fn highlighted(&self, pos: &Coordinates, painted: String) -> String {
+        if self.highlight == Some(*pos) {
+            painted.negative().to_string()
+        } else {
+            painted
+        }
+    }
+
+    /// A closed cell with its chance of a landmine in percent, on a colour
+    /// from green for safe to red for a certain landmine.
+    fn heat_symbol(&self, chance: f64) -> String {
+        let width = self.config.char_width as usize;
+        let symbol = if chance >= 1.0 {
+            self.config.symbols.landmine.clone()
+        } else if chance <= 0.0 {
+            format!("{:>width$}", 0)
+        } else {
+            let percent = (chance * 100.0).round().clamp(1.0, 99.0);
+            format!("{:>width$}", percent)
+        };
+        let background = Color::Rgb {
+            r: (255.0 * chance.min(1.0) * 2.0).min(255.0) as u8,
+            g: (255.0 * (1.0 - chance.max(0.0)) * 2.0).min(255.0) as u8,
+            b: 0,
+        };
+        symbol.black().on(background).to_string()
+    }
+
+    /// Shows the chance of a landmine over every closed cell, as worked
+    /// out by the solver, or stops showing it with `None`.
+    pub fn set_heatmap(&mut self, heatmap: Option<Vec<Vec<f64>>>) {
+        self.heatmap = heatmap;
+    }
+
+    /// Draws the cell at `pos` in reverse video, to point it out.
+    pub fn set_highlight(&mut self, pos: Option<Coordinates>) {
+        self.highlight = pos;
+    }
+
+    /// Colours `symbol`, leaving the terminal colours alone where the
+    /// colour is [`Color::Reset`].
+    fn paint(&self, symbol: String, foreground: Color) -> String {
+        let background = self.config.board_background_colour;
+        match (foreground, background) {
+            (Color::Reset, Color::Reset) => symbol,
+            (Color::Reset, _) => symbol.on(background).to_string(),
+            (_, Color::Reset) => symbol.with(foreground).to_string(),
+            _ => symbol.with(foreground).on(background).to_string(),
+        }
+    }
+
+    /// Draws the board with other symbols and colours from now on.
+    pub fn set_style(&mut self, symbols: Symbols, background: Color, numbers: Color) {
+        self.config.symbols = symbols;
+        self.config.board_background_colour = background;
+        self.config.number_colour = numbers;
+        self.draw_border();
+    }
+
+    /// Writes `text` into the status line below the board.
+    pub fn draw_status(&mut self, text: &str) {
+        let row = &mut self.framebuffer[self.config.height + 2];
+        for symbol in row.iter_mut() {
+            symbol.clear();
+        }
+        row[0] = text.to_string();
+    }
+
     pub fn print(&mut self) {
         let mut f = std::io::stdout();
         self.draw_field().unwrap();
@@ -562,11 +759,84 @@ impl Board {
         Ok(())
     }
 
+    pub fn mark(&self, pos: &Coordinates) -> Result<Mark, BoardError> {
+        if self.flags.get(pos)? {
+            Ok(Mark::Flag)
+        } else if self.questions.get(pos)? {
+            Ok(Mark::Question)
+        } else {
+            Ok(Mark::None)
+        }
+    }
+
+    /// Replaces the mark on `pos`.
+    pub fn set_mark(&mut self, pos: &Coordinates, mark: Mark) -> Result<(), BoardError> {
+        self.flags.remove(pos)?;
+        self.questions.remove(pos)?;
+        match mark {
+            Mark::None => Ok(()),
+            Mark::Flag => self.flags.add(pos),
+            Mark::Question => self.questions.add(pos),
+        }
+    }
+
+    pub fn flag_count(&self) -> &usize {
+        self.flags.get_count()
+    }
+
     pub fn have_flag(&self, pos: &Coordinates) -> Result<bool, BoardError> {
         self.flags.get(pos)
     }
 
-    pub fn click(&mut self, pos: &Coordinates) -> Result<(), BoardError> {
+    pub fn opened_count(&self) -> &usize {
+        self.opened.get_count()
+    }
+
+    pub fn is_opened(&self, pos: &Coordinates) -> Result<bool, BoardError> {
+        self.opened.get(pos)
+    }
+
+    /// Opens the cell at `pos`. Clicking a cell with no adjacent landmines
+    /// also opens the whole connected empty region and its numbered border.
+    /// Flagged cells are never opened, while a question mark is cleared.
+    pub fn click(&mut self, pos: &Coordinates) -> Result<ClickResult, BoardError> {
+        let mut result = ClickResult::default();
+        if self.flags.get(pos)? || self.opened.get(pos)? {
+            return Ok(result);
+        }
+
+        let width = self.config.width;
+        let height = self.config.height;
+        let mut stack = vec![*pos];
+        while let Some(current) = stack.pop() {
+            if self.flags.get(&current)? || self.opened.get(&current)? {
+                continue;
+            }
+            self.opened.add(&current)?;
+            if self.questions.get(&current)? {
+                self.questions.remove(&current)?;
+                result.questions.push(current);
+            }
+            result.opened.push(current);
+
+            if self.landmines.get(&current)? {
+                result.landmine = Some(current);
+                continue;
+            }
+
+            if self.numbers.get(&current)? == 0 {
+                for neighbour in current.neighbours(width, height) {
+                    if !self.opened.get(&neighbour)? {
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Opens only the cell at `pos`, without opening any region around it.
+    pub fn open_cell(&mut self, pos: &Coordinates) -> Result<(), BoardError> {
         self.opened.add(pos)?;
         Ok(())
     }
@@ -576,10 +846,125 @@ impl Board {
         Ok(())
     }
 
+    /// Number of flags on the cells surrounding `pos`.
+    pub fn flags_around(&self, pos: &Coordinates) -> Result<usize, BoardError> {
+        let mut count = 0;
+        for neighbour in pos.neighbours(self.config.width, self.config.height) {
+            if self.flags.get(&neighbour)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Opens every unflagged neighbour of an opened number once the number
+    /// of flags around it matches. A wrongly placed flag means a landmine
+    /// gets opened.
+    pub fn chord(&mut self, pos: &Coordinates) -> Result<ClickResult, BoardError> {
+        let mut result = ClickResult::default();
+        let number = self.numbers.get(pos)?;
+        if !self.opened.get(pos)? || number == 0 || self.flags_around(pos)? != number as usize {
+            return Ok(result);
+        }
+
+        for neighbour in pos.neighbours(self.config.width, self.config.height) {
+            result.merge(self.click(&neighbour)?);
+        }
+        Ok(result)
+    }
+
+    /// The board's 3BV: the fewest clicks that clear it without flags. That
+    /// is one per opening plus one per number that no opening reveals.
+    pub fn three_bv(&self) -> Result<usize, BoardError> {
+        let width = self.config.width;
+        let height = self.config.height;
+        let mut counted = vec![vec![false; width]; height];
+        let mut count = 0;
+
+        for y in 0..height {
+            for x in 0..width {
+                let pos = Coordinates::new(x, y);
+                if counted[y][x] || self.landmines.get(&pos)? || self.numbers.get(&pos)? > 0 {
+                    continue;
+                }
+                count += 1;
+                let mut stack = vec![pos];
+                counted[y][x] = true;
+                while let Some(current) = stack.pop() {
+                    if self.numbers.get(&current)? > 0 {
+                        continue;
+                    }
+                    for neighbour in current.neighbours(width, height) {
+                        if !counted[neighbour.y][neighbour.x] {
+                            counted[neighbour.y][neighbour.x] = true;
+                            stack.push(neighbour);
+                        }
+                    }
+                }
+            }
+        }
+
+        for (y, row) in counted.iter().enumerate() {
+            for (x, counted) in row.iter().enumerate() {
+                if !counted && !self.landmines.get(&Coordinates::new(x, y))? {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// True once every cell without a landmine has been opened.
+    pub fn is_cleared(&self) -> bool {
+        let cells = self.config.width * self.config.height;
+        self.exploded.is_none() && *self.opened.get_count() == cells - self.landmines.get_count()
+    }
+
+    /// True when every landmine is flagged and no other cell is.
+    pub fn all_landmines_flagged(&self) -> bool {
+        self.flags.get_count() == self.landmines.get_count()
+            && self
+                .landmines
+                .get_field()
+                .iter()
+                .flatten()
+                .zip(self.flags.get_field().iter().flatten())
+                .all(|(landmine, flag)| landmine == flag)
+    }
+
+    /// Shows every landmine and marks wrongly placed flags. `exploded` is the
+    /// landmine that ended the game, if any.
+    pub fn reveal_landmines(&mut self, exploded: Option<Coordinates>) {
+        self.revealed = true;
+        self.exploded = exploded;
+    }
+
+    /// Takes back [`Board::reveal_landmines`].
+    pub fn hide_landmines(&mut self) {
+        self.revealed = false;
+        self.exploded = None;
+    }
+
+    /// Flags every landmine that is not flagged yet. Returns the cells that
+    /// got a new flag.
+    pub fn flag_landmines(&mut self) -> Result<Vec<Coordinates>, BoardError> {
+        let mut flagged = vec![];
+        for j in 0..self.config.height {
+            for i in 0..self.config.width {
+                let pos = Coordinates::new(i, j);
+                if self.landmines.get(&pos)? && !self.flags.get(&pos)? {
+                    self.flags.add(&pos)?;
+                    flagged.push(pos);
+                }
+            }
+        }
+        Ok(flagged)
+    }
+
     pub fn get(&self, pos: &Coordinates) -> Result<ItemType, BoardError> {
         if self.landmines.get(pos)? {
             Ok(ItemType::Landmine)
-        } else if self.numbers.get(pos)? > 1 {
+        } else if self.numbers.get(pos)? > 0 {
             Ok(ItemType::Number(self.numbers.get(pos)?))
         } else {
             Ok(ItemType::Nothing)
@@ -587,7 +972,7 @@ impl Board {
     }
 }
 
-impl<'a> std::fmt::Display for Board {
+impl std::fmt::Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for row in &self.framebuffer {
             for symbol in row {
@@ -614,4 +999,136 @@ pub fn test1() -> Result<(), BoardError> {
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::*;
+    use crate::engine::test::test_board;
+
+    #[test]
+    fn click_number_opens_single_cell() {
+        let mut board = test_board(5, 5, &[Coordinates::new(0, 0)]);
+
+        let result = board.click(&Coordinates::new(1, 1)).unwrap();
+        assert_eq!(result.opened, vec![Coordinates::new(1, 1)]);
+        assert!(!result.hit_landmine());
+    }
+
+    #[test]
+    fn click_empty_opens_region_and_border() {
+        let mut board = test_board(5, 5, &[Coordinates::new(0, 0)]);
+
+        let result = board.click(&Coordinates::new(4, 4)).unwrap();
+        assert_eq!(result.opened.len(), 24);
+        assert!(!board.is_opened(&Coordinates::new(0, 0)).unwrap());
+        assert!(board.is_opened(&Coordinates::new(1, 0)).unwrap());
+        assert!(board.is_opened(&Coordinates::new(1, 1)).unwrap());
+
+        let again = board.click(&Coordinates::new(4, 4)).unwrap();
+        assert!(again.opened.is_empty());
+    }
+
+    #[test]
+    fn click_skips_flagged_cells() {
+        let mut board = test_board(5, 5, &[Coordinates::new(0, 0)]);
+        board.add_flag(&Coordinates::new(3, 3)).unwrap();
+
+        let result = board.click(&Coordinates::new(3, 3)).unwrap();
+        assert!(result.opened.is_empty());
+
+        let result = board.click(&Coordinates::new(4, 4)).unwrap();
+        assert_eq!(result.opened.len(), 23);
+        assert!(!board.is_opened(&Coordinates::new(3, 3)).unwrap());
+    }
+
+    #[test]
+    fn cleared_after_opening_all_safe_cells() {
+        let mut board = test_board(3, 3, &[Coordinates::new(0, 0), Coordinates::new(2, 2)]);
+        board.click(&Coordinates::new(2, 0)).unwrap();
+        assert!(!board.is_cleared());
+
+        board.click(&Coordinates::new(0, 2)).unwrap();
+        assert!(board.is_cleared());
+    }
+
+    #[test]
+    fn all_landmines_flagged() {
+        let mut board = test_board(3, 3, &[Coordinates::new(0, 0)]);
+        board.add_flag(&Coordinates::new(0, 0)).unwrap();
+        assert!(board.all_landmines_flagged());
+
+        board.add_flag(&Coordinates::new(1, 1)).unwrap();
+        assert!(!board.all_landmines_flagged());
+    }
+
+    #[test]
+    fn reveal_marks_landmines_and_wrong_flags() {
+        let mut board = test_board(3, 1, &[Coordinates::new(0, 0), Coordinates::new(2, 0)]);
+        board.add_flag(&Coordinates::new(1, 0)).unwrap();
+        board.click(&Coordinates::new(0, 0)).unwrap();
+        board.reveal_landmines(Some(Coordinates::new(0, 0)));
+
+        assert_eq!(board.cell_symbol(&Coordinates::new(0, 0)).unwrap(), "💥");
+        assert_eq!(board.cell_symbol(&Coordinates::new(1, 0)).unwrap(), "❌");
+        assert_eq!(board.cell_symbol(&Coordinates::new(2, 0)).unwrap(), "💣");
+    }
+
+    #[test]
+    fn set_landmines_recalculates_numbers() {
+        let mut board = test_board(3, 3, &[]);
+        board.set_landmines(&[Coordinates::new(0, 0)]).unwrap();
+
+        assert_eq!(*board.landmine_count(), 1);
+        assert_eq!(
+            board.get(&Coordinates::new(1, 1)).unwrap(),
+            ItemType::Number(1)
+        );
+        assert_eq!(
+            board.get(&Coordinates::new(2, 2)).unwrap(),
+            ItemType::Nothing
+        );
+    }
+
+    #[test]
+    fn chord_opens_neighbours_when_flags_match() {
+        let mut board = test_board(3, 3, &[Coordinates::new(0, 0)]);
+        board.click(&Coordinates::new(1, 1)).unwrap();
+
+        let result = board.chord(&Coordinates::new(1, 1)).unwrap();
+        assert!(result.opened.is_empty());
+
+        board.add_flag(&Coordinates::new(0, 0)).unwrap();
+        let result = board.chord(&Coordinates::new(1, 1)).unwrap();
+        assert!(!result.hit_landmine());
+        assert!(board.is_cleared());
+    }
+
+    #[test]
+    fn chord_with_wrong_flag_hits_landmine() {
+        let mut board = test_board(3, 3, &[Coordinates::new(0, 0)]);
+        board.click(&Coordinates::new(1, 1)).unwrap();
+        board.add_flag(&Coordinates::new(2, 2)).unwrap();
+
+        let result = board.chord(&Coordinates::new(1, 1)).unwrap();
+        assert_eq!(result.landmine, Some(Coordinates::new(0, 0)));
+    }
+
+    #[test]
+    fn three_bv_counts_openings_and_lone_numbers() {
+        let board = test_board(3, 3, &[Coordinates::new(0, 0)]);
+        assert_eq!(board.three_bv().unwrap(), 1);
+
+        let board = test_board(3, 1, &[Coordinates::new(1, 0)]);
+        assert_eq!(board.three_bv().unwrap(), 2);
+
+        let board = test_board(5, 1, &[Coordinates::new(1, 0)]);
+        assert_eq!(board.three_bv().unwrap(), 2);
+    }
+
+    #[test]
+    fn click_landmine() {
+        let mut board = test_board(3, 3, &[Coordinates::new(1, 1)]);
+
+        let result = board.click(&Coordinates::new(1, 1)).unwrap();
+        assert_eq!(result.landmine, Some(Coordinates::new(1, 1)));
+        assert_eq!(result.opened, vec![Coordinates::new(1, 1)]);
+    }
+}
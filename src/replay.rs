@@ -0,0 +1,846 @@
+//! Recordings of played games: the board as it was at the start, with its
+//! landmines, and every action with the time it was taken.
+
+use crate::boardtext::{board_to_text, parse_board};
+use crate::engine::{Action, Clicks, Engine, GameOptions, GameState};
+use crate::mineboard::{Board, BoardError};
+use crate::utils::{self, Coordinates, Seed};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const HEADER: &str = "terminal_minesweeper replay";
+
+/// Version written by [`Replay::to_string`].
+pub const REPLAY_VERSION: u32 = 2;
+
+/// Events kept in one replay before cursor moves are dropped, so a game
+/// left open for hours still gives a file of about a megabyte. Moves that
+/// change the board are always kept.
+pub const MAX_EVENTS: usize = 50_000;
+
+/// Replays kept in [`default_dir`]. The oldest are removed.
+pub const MAX_REPLAYS: usize = 100;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    NotAReplay,
+    UnsupportedVersion(u32),
+    MissingKey(&'static str),
+    InvalidValue { key: String, value: String },
+    InvalidEvent(usize),
+    Board(BoardError),
+}
+
+impl Error for ReplayError {}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "{}", e),
+            ReplayError::NotAReplay => write!(f, "not a replay"),
+            ReplayError::UnsupportedVersion(version) => write!(
+                f,
+                "replay version {} is newer than the supported version {}",
+                version, REPLAY_VERSION
+            ),
+            ReplayError::MissingKey(key) => write!(f, "replay has no '{}'", key),
+            ReplayError::InvalidValue { key, value } => {
+                write!(f, "replay has an invalid {} '{}'", key, value)
+            }
+            ReplayError::InvalidEvent(line) => write!(f, "replay line {} is invalid", line),
+            ReplayError::Board(e) => write!(f, "replay board: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl From<BoardError> for ReplayError {
+    fn from(e: BoardError) -> Self {
+        ReplayError::Board(e)
+    }
+}
+
+/// Something the player did.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplayAction {
+    /// The cursor moved onto a cell.
+    Move(Coordinates),
+    Reveal(Coordinates),
+    Flag(Coordinates),
+    Chord(Coordinates),
+    Undo,
+    Redo,
+}
+
+impl ReplayAction {
+    /// The same action on an engine, for those that change the board.
+    pub fn engine_action(&self) -> Option<Action> {
+        match self {
+            ReplayAction::Reveal(pos) => Some(Action::Reveal(*pos)),
+            ReplayAction::Flag(pos) => Some(Action::Flag(*pos)),
+            ReplayAction::Chord(pos) => Some(Action::Chord(*pos)),
+            ReplayAction::Move(_) | ReplayAction::Undo | ReplayAction::Redo => None,
+        }
+    }
+
+    /// The cell the action is on, if any.
+    pub fn pos(&self) -> Option<Coordinates> {
+        match self {
+            ReplayAction::Move(pos)
+            | ReplayAction::Reveal(pos)
+            | ReplayAction::Flag(pos)
+            | ReplayAction::Chord(pos) => Some(*pos),
+            ReplayAction::Undo | ReplayAction::Redo => None,
+        }
+    }
+
+    /// Plays the action on `engine`. Cursor moves leave it alone.
+    pub fn apply(&self, engine: &mut Engine) -> Result<(), BoardError> {
+        match self {
+            ReplayAction::Undo => {
+                engine.undo()?;
+            }
+            ReplayAction::Redo => {
+                engine.redo()?;
+            }
+            action => {
+                if let Some(action) = action.engine_action() {
+                    engine.apply(&action)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ReplayAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, pos) = match self {
+            ReplayAction::Move(pos) => ("move", Some(pos)),
+            ReplayAction::Reveal(pos) => ("reveal", Some(pos)),
+            ReplayAction::Flag(pos) => ("flag", Some(pos)),
+            ReplayAction::Chord(pos) => ("chord", Some(pos)),
+            ReplayAction::Undo => ("undo", None),
+            ReplayAction::Redo => ("redo", None),
+        };
+        match pos {
+            Some(pos) => write!(f, "{}\t{}\t{}", name, pos.x, pos.y),
+            None => write!(f, "{}", name),
+        }
+    }
+}
+
+impl FromStr for ReplayAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split('\t');
+        let name = fields.next().ok_or(())?;
+        let mut pos = || -> Result<Coordinates, ()> {
+            let x = fields.next().ok_or(())?.parse().map_err(|_| ())?;
+            let y = fields.next().ok_or(())?.parse().map_err(|_| ())?;
+            Ok(Coordinates::new(x, y))
+        };
+        let action = match name {
+            "move" => ReplayAction::Move(pos()?),
+            "reveal" => ReplayAction::Reveal(pos()?),
+            "flag" => ReplayAction::Flag(pos()?),
+            "chord" => ReplayAction::Chord(pos()?),
+            "undo" => ReplayAction::Undo,
+            "redo" => ReplayAction::Redo,
+            _ => return Err(()),
+        };
+        match fields.next() {
+            Some(_) => Err(()),
+            None => Ok(action),
+        }
+    }
+}
+
+/// An action and when it was taken, counted from the start of the
+/// recording.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayEvent {
+    pub time: Duration,
+    pub action: ReplayAction,
+}
+
+/// A recorded game.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub seed: Seed,
+    pub options: GameOptions,
+    pub landmine_count: usize,
+    /// The board when recording started, already holding the landmines the
+    /// game ended with.
+    pub board: Board,
+    /// Time on the clock when recording started, for resumed games.
+    pub start_time: Duration,
+    /// Time into the recording the clock started at the first reveal, zero
+    /// when it was already running.
+    pub clock_offset: Duration,
+    /// How the game ended and the time on the clock then.
+    pub result: GameState,
+    pub end_time: Duration,
+    pub events: Vec<ReplayEvent>,
+    /// Set when cursor moves were dropped to keep to [`MAX_EVENTS`].
+    pub truncated: bool,
+}
+
+impl Replay {
+    /// What the game clock showed `time` into the recording.
+    pub fn clock_at(&self, time: Duration) -> Duration {
+        self.start_time + time.saturating_sub(self.clock_offset)
+    }
+
+    /// Time into the recording when the game clock showed `clock`, the
+    /// reverse of [`Replay::clock_at`].
+    pub fn time_at(&self, clock: Duration) -> Duration {
+        clock.saturating_sub(self.start_time) + self.clock_offset
+    }
+
+    /// A new game at the start of the replay.
+    pub fn engine(&self) -> Engine {
+        Engine::restore(
+            self.board.clone(),
+            self.landmine_count,
+            GameOptions {
+                seed: Some(self.seed),
+                ..self.options.clone()
+            },
+            true,
+            self.start_time,
+            0,
+            Clicks::default(),
+        )
+    }
+
+    /// Plays every event again and checks that the game ends as recorded.
+    pub fn verify(&self) -> Result<bool, BoardError> {
+        let mut engine = self.engine();
+        for event in &self.events {
+            event.action.apply(&mut engine)?;
+        }
+        Ok(engine.state() == &self.result)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ReplayError> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(ReplayError::NotAReplay);
+        }
+
+        let mut values = vec![];
+        for (_, line) in lines.by_ref() {
+            if line == "board" {
+                break;
+            }
+            match line.split_once('=') {
+                Some((key, value)) => values.push((key, value)),
+                None => return Err(ReplayError::NotAReplay),
+            }
+        }
+        let mut board = vec![];
+        for (_, line) in lines.by_ref() {
+            if line == "events" {
+                break;
+            }
+            board.push(line);
+        }
+
+        let get = |key: &'static str| {
+            values
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| *v)
+                .ok_or(ReplayError::MissingKey(key))
+        };
+        fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, ReplayError> {
+            value.parse().map_err(|_| ReplayError::InvalidValue {
+                key: key.to_string(),
+                value: value.to_string(),
+            })
+        }
+        let millis = |key: &'static str| -> Result<Duration, ReplayError> {
+            Ok(Duration::from_millis(parse(key, get(key)?)?))
+        };
+
+        let version: u32 = parse("version", get("version")?)?;
+        if version > REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let seed = parse("seed", get("seed")?)?;
+        let options = GameOptions {
+            flag_win: parse("flag_win", get("flag_win")?)?,
+            practice: parse("practice", get("practice")?)?,
+            question_marks: parse("question_marks", get("question_marks")?)?,
+            seed: Some(seed),
+            ..GameOptions::default()
+        };
+        let result = match get("result")? {
+            "win" => GameState::Win,
+            "lost" => GameState::HitMine,
+            value => {
+                return Err(ReplayError::InvalidValue {
+                    key: "result".to_string(),
+                    value: value.to_string(),
+                })
+            }
+        };
+
+        let board = parse_board(&board.join("\n"))?;
+        let config = board.get_config();
+        let on_board = |action: &ReplayAction| {
+            action
+                .pos()
+                .is_none_or(|pos| pos.x < config.width && pos.y < config.height)
+        };
+
+        let mut events = vec![];
+        for (i, line) in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let event = line
+                .split_once('\t')
+                .and_then(|(time, action)| {
+                    Some(ReplayEvent {
+                        time: Duration::from_millis(time.parse().ok()?),
+                        action: action.parse().ok()?,
+                    })
+                })
+                .filter(|event| on_board(&event.action))
+                .ok_or(ReplayError::InvalidEvent(i + 1))?;
+            events.push(event);
+        }
+
+        Ok(Replay {
+            seed,
+            options,
+            landmine_count: parse("landmines", get("landmines")?)?,
+            board,
+            start_time: millis("start_ms")?,
+            // Version 1 did not know when the clock started.
+            clock_offset: match get("clock_ms") {
+                Ok(_) => millis("clock_ms")?,
+                Err(_) => Duration::ZERO,
+            },
+            result,
+            end_time: millis("end_ms")?,
+            events,
+            truncated: parse("truncated", get("truncated")?)?,
+        })
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = match self.result {
+            GameState::Win => "win",
+            _ => "lost",
+        };
+        writeln!(f, "{}\nversion={}", HEADER, REPLAY_VERSION)?;
+        writeln!(f, "seed={}", self.seed)?;
+        writeln!(f, "landmines={}", self.landmine_count)?;
+        writeln!(f, "flag_win={}", self.options.flag_win)?;
+        writeln!(f, "practice={}", self.options.practice)?;
+        writeln!(f, "question_marks={}", self.options.question_marks)?;
+        writeln!(f, "start_ms={}", self.start_time.as_millis())?;
+        writeln!(f, "clock_ms={}", self.clock_offset.as_millis())?;
+        writeln!(f, "result={}", result)?;
+        writeln!(f, "end_ms={}", self.end_time.as_millis())?;
+        writeln!(f, "truncated={}", self.truncated)?;
+        writeln!(f, "board")?;
+        write!(f, "{}", board_to_text(&self.board).map_err(|_| fmt::Error)?)?;
+        writeln!(f, "events")?;
+        for event in &self.events {
+            writeln!(f, "{}\t{}", event.time.as_millis(), event.action)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records a game as it is played.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    started: Instant,
+    start_time: Duration,
+    board: Board,
+    events: Vec<ReplayEvent>,
+    truncated: bool,
+    /// The cell of the last cursor move, so staying on a cell is not
+    /// recorded again.
+    cursor: Option<Coordinates>,
+    /// Cursor moves among `events`, which can be dropped to make room.
+    moves: usize,
+}
+
+impl Recorder {
+    /// Starts recording `engine` as it is now.
+    pub fn new(engine: &Engine) -> Self {
+        Self {
+            started: Instant::now(),
+            start_time: engine.elapsed(),
+            board: engine.board().clone(),
+            events: vec![],
+            truncated: false,
+            cursor: None,
+            moves: 0,
+        }
+    }
+
+    pub fn record(&mut self, action: ReplayAction) {
+        if let ReplayAction::Move(pos) = action {
+            if self.cursor.replace(pos) == Some(pos) {
+                return;
+            }
+        }
+        if self.events.len() >= MAX_EVENTS && self.moves > 0 {
+            self.drop_old_moves();
+        }
+        let is_move = matches!(action, ReplayAction::Move(_));
+        if is_move && self.events.len() >= MAX_EVENTS {
+            // Every other action is needed to play the game again, so
+            // without moves left to drop the recording grows.
+            self.truncated = true;
+            return;
+        }
+        self.moves += is_move as usize;
+        // Kept to the millisecond, as written.
+        let millis = self.started.elapsed().as_millis() as u64;
+        self.events.push(ReplayEvent {
+            time: Duration::from_millis(millis),
+            action,
+        });
+    }
+
+    /// Makes room by dropping the oldest tenth of the events, taken from the
+    /// cursor moves only. Done in one pass, so a long game at the limit
+    /// does not pay for it on every action.
+    fn drop_old_moves(&mut self) {
+        let mut dropping = (MAX_EVENTS / 10).min(self.moves);
+        self.moves -= dropping;
+        self.truncated = true;
+        self.events.retain(|event| {
+            let drop = dropping > 0 && matches!(event.action, ReplayAction::Move(_));
+            dropping -= drop as usize;
+            !drop
+        });
+    }
+
+    /// The replay of the finished game of `engine`. `None` while the game
+    /// is not finished.
+    pub fn finish(&self, engine: &Engine) -> Result<Option<Replay>, BoardError> {
+        let result = match engine.state() {
+            GameState::Win | GameState::HitMine => engine.state().clone(),
+            GameState::Playing | GameState::Quit => return Ok(None),
+        };
+        // Kept to the millisecond, like the event times.
+        let clock_offset = engine.started_at().map_or(Duration::ZERO, |start| {
+            let offset = start.saturating_duration_since(self.started);
+            Duration::from_millis(offset.as_millis() as u64)
+        });
+        let mut board = self.board.clone();
+        board.set_landmines(&engine.board().landmine_positions()?)?;
+        Ok(Some(Replay {
+            seed: *engine.seed(),
+            options: engine.options().clone(),
+            landmine_count: *engine.landmine_count(),
+            board,
+            start_time: self.start_time,
+            clock_offset,
+            result,
+            end_time: engine.elapsed(),
+            events: self.events.clone(),
+            truncated: self.truncated,
+        }))
+    }
+}
+
+/// A replay being watched: the game as it was after the first
+/// [`Playback::position`] events.
+#[derive(Clone, Debug)]
+pub struct Playback {
+    replay: Replay,
+    engine: Engine,
+    position: usize,
+    cursor: Option<Coordinates>,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            engine: replay.engine(),
+            replay,
+            position: 0,
+            cursor: None,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+
+    /// Events played so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.position == self.replay.events.len()
+    }
+
+    /// The cell the cursor was on.
+    pub fn cursor(&self) -> Option<Coordinates> {
+        self.cursor
+    }
+
+    /// Time of the last event played.
+    pub fn time(&self) -> Duration {
+        match self.position {
+            0 => Duration::ZERO,
+            position => self.replay.events[position - 1].time,
+        }
+    }
+
+    /// Time of the next event to play.
+    pub fn next_time(&self) -> Option<Duration> {
+        self.replay
+            .events
+            .get(self.position)
+            .map(|event| event.time)
+    }
+
+    /// Time of the last event.
+    pub fn duration(&self) -> Duration {
+        self.replay
+            .events
+            .last()
+            .map_or(Duration::ZERO, |event| event.time)
+    }
+
+    /// Plays the next event. Returns false at the end.
+    pub fn next_event(&mut self) -> Result<bool, BoardError> {
+        let event = match self.replay.events.get(self.position) {
+            Some(event) => event,
+            None => return Ok(false),
+        };
+        event.action.apply(&mut self.engine)?;
+        self.cursor = match event.action {
+            ReplayAction::Move(pos)
+            | ReplayAction::Reveal(pos)
+            | ReplayAction::Flag(pos)
+            | ReplayAction::Chord(pos) => Some(pos),
+            ReplayAction::Undo | ReplayAction::Redo => self.cursor,
+        };
+        self.position += 1;
+        Ok(true)
+    }
+
+    /// Plays events up to and including the next one that is not a cursor
+    /// move. Returns false at the end.
+    pub fn step_forward(&mut self) -> Result<bool, BoardError> {
+        if !self.next_event()? {
+            return Ok(false);
+        }
+        while matches!(self.last_action(), Some(ReplayAction::Move(_))) {
+            if !self.next_event()? {
+                break;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Goes back to just after the action before the last one, skipping
+    /// cursor moves. Returns false at the start.
+    pub fn step_back(&mut self) -> Result<bool, BoardError> {
+        if self.position == 0 {
+            return Ok(false);
+        }
+        let position = self.replay.events[..self.position - 1]
+            .iter()
+            .rposition(|event| !matches!(event.action, ReplayAction::Move(_)))
+            .map_or(0, |i| i + 1);
+        self.seek(position)?;
+        Ok(true)
+    }
+
+    /// Goes to just after the first `position` events. Going back plays
+    /// the game again from the start.
+    pub fn seek(&mut self, position: usize) -> Result<(), BoardError> {
+        let position = position.min(self.replay.events.len());
+        if position < self.position {
+            self.engine = self.replay.engine();
+            self.position = 0;
+            self.cursor = None;
+        }
+        while self.position < position {
+            self.next_event()?;
+        }
+        Ok(())
+    }
+
+    /// Goes to just after every event taken by `time`.
+    pub fn seek_time(&mut self, time: Duration) -> Result<(), BoardError> {
+        let position = self
+            .replay
+            .events
+            .partition_point(|event| event.time <= time);
+        self.seek(position)
+    }
+
+    fn last_action(&self) -> Option<&ReplayAction> {
+        self.replay
+            .events
+            .get(self.position.checked_sub(1)?)
+            .map(|event| &event.action)
+    }
+}
+
+/// Where replays are kept, one file per game.
+pub fn default_dir() -> Option<PathBuf> {
+    Some(crate::utils::data_dir()?.join("replays"))
+}
+
+/// Writes `replay` into `dir` under a name that sorts by the time it was
+/// written, then removes the oldest past [`MAX_REPLAYS`]. Returns the path
+/// written.
+pub fn save(replay: &Replay, dir: &Path) -> Result<PathBuf, ReplayError> {
+    fs::create_dir_all(dir)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis())
+        .unwrap_or(0);
+    let path = dir.join(format!("{:016}-{}.txt", now, replay.seed));
+    utils::write_atomically(&path, &replay.to_string())?;
+
+    let mut replays = list(dir)?;
+    while replays.len() > MAX_REPLAYS {
+        fs::remove_file(replays.remove(0))?;
+    }
+    Ok(path)
+}
+
+/// Replays in `dir`, oldest first.
+pub fn list(dir: &Path) -> Result<Vec<PathBuf>, ReplayError> {
+    let mut replays: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(e.into()),
+    };
+    replays.sort();
+    Ok(replays)
+}
+
+pub fn load(path: &Path) -> Result<Replay, ReplayError> {
+    Replay::parse(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::test::{find_cell, test_engine};
+    use crate::mineboard::ItemType;
+
+    fn recorded_game() -> (Engine, Recorder) {
+        let mut engine = test_engine(9, 9, 10);
+        let mut recorder = Recorder::new(&engine);
+        let actions = [
+            ReplayAction::Move(Coordinates::new(4, 4)),
+            ReplayAction::Reveal(Coordinates::new(4, 4)),
+            ReplayAction::Flag(Coordinates::new(0, 0)),
+        ];
+        for action in actions {
+            action.apply(&mut engine).unwrap();
+            recorder.record(action);
+        }
+        let landmine = find_cell(&engine, |board, pos| {
+            board.get(pos).unwrap() == ItemType::Landmine && !board.have_flag(pos).unwrap()
+        });
+        let action = ReplayAction::Reveal(landmine);
+        action.apply(&mut engine).unwrap();
+        recorder.record(action);
+        (engine, recorder)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (engine, recorder) = recorded_game();
+        let replay = recorder.finish(&engine).unwrap().unwrap();
+        assert_eq!(replay.events.len(), 4);
+        assert_eq!(
+            replay.events[0].action,
+            ReplayAction::Move(Coordinates::new(4, 4))
+        );
+        assert!(replay.verify().unwrap());
+
+        let text = replay.to_string();
+        let parsed = Replay::parse(&text).unwrap();
+        assert_eq!(parsed.events, replay.events);
+        assert_eq!(parsed.result, GameState::HitMine);
+        assert_eq!(parsed.to_string(), text);
+        assert!(parsed.verify().unwrap());
+    }
+
+    #[test]
+    fn unfinished_games_have_no_replay() {
+        let engine = test_engine(9, 9, 10);
+        assert!(Recorder::new(&engine).finish(&engine).unwrap().is_none());
+    }
+
+    #[test]
+    fn staying_on_a_cell_is_recorded_once() {
+        let (engine, mut recorder) = recorded_game();
+        recorder.record(ReplayAction::Move(Coordinates::new(2, 2)));
+        recorder.record(ReplayAction::Move(Coordinates::new(2, 2)));
+        recorder.record(ReplayAction::Flag(Coordinates::new(2, 2)));
+        recorder.record(ReplayAction::Move(Coordinates::new(2, 2)));
+        let replay = recorder.finish(&engine).unwrap().unwrap();
+        assert_eq!(replay.events.len(), 6);
+    }
+
+    #[test]
+    fn events_are_bounded() {
+        let (engine, mut recorder) = recorded_game();
+        for i in 0..MAX_EVENTS {
+            recorder.record(ReplayAction::Move(Coordinates::new(i % 2, 1)));
+        }
+        // The board actions are kept, while the oldest moves make way for
+        // later ones.
+        let flag = ReplayAction::Flag(Coordinates::new(8, 8));
+        recorder.record(flag.clone());
+        let replay = recorder.finish(&engine).unwrap().unwrap();
+        // A tenth went when the limit was hit, then four moves and the flag
+        // came in.
+        assert_eq!(replay.events.len(), MAX_EVENTS - MAX_EVENTS / 10 + 5);
+        assert!(replay.truncated);
+        let kept: Vec<&ReplayAction> = replay.events.iter().map(|e| &e.action).collect();
+        assert_eq!(kept[0], &ReplayAction::Reveal(Coordinates::new(4, 4)));
+        assert_eq!(kept[1], &ReplayAction::Flag(Coordinates::new(0, 0)));
+        assert!(matches!(kept[2], ReplayAction::Reveal(_)));
+        assert_eq!(kept.last(), Some(&&flag));
+        assert!(replay.verify().unwrap());
+    }
+
+    #[test]
+    fn times_follow_the_game_clock() {
+        // The clock starts at the first reveal, well after the first move.
+        let mut engine = test_engine(9, 9, 10);
+        let mut recorder = Recorder::new(&engine);
+        recorder.record(ReplayAction::Move(Coordinates::new(4, 4)));
+        std::thread::sleep(Duration::from_millis(50));
+        let start = ReplayAction::Reveal(Coordinates::new(4, 4));
+        start.apply(&mut engine).unwrap();
+        recorder.record(start);
+        std::thread::sleep(Duration::from_millis(20));
+        let landmine = find_cell(&engine, |board, pos| {
+            board.get(pos).unwrap() == ItemType::Landmine
+        });
+        let action = ReplayAction::Reveal(landmine);
+        action.apply(&mut engine).unwrap();
+        recorder.record(action);
+
+        let replay = recorder.finish(&engine).unwrap().unwrap();
+        assert!(replay.clock_offset >= Duration::from_millis(50));
+        assert_eq!(replay.clock_at(replay.events[0].time), Duration::ZERO);
+        assert_eq!(replay.time_at(Duration::ZERO), replay.clock_offset);
+        let parsed = Replay::parse(&replay.to_string()).unwrap();
+        assert_eq!(parsed.clock_offset, replay.clock_offset);
+
+        // Second 0 on the game clock is the first reveal, not the move.
+        let mut playback = Playback::new(replay);
+        playback
+            .seek_time(playback.replay().time_at(Duration::ZERO))
+            .unwrap();
+        assert_eq!(playback.position(), 2);
+        assert!(playback.engine().is_started());
+    }
+
+    #[test]
+    fn playback_steps_and_seeks() {
+        let (engine, recorder) = recorded_game();
+        let replay = recorder.finish(&engine).unwrap().unwrap();
+        let mut playback = Playback::new(replay);
+        let opened = |playback: &Playback| {
+            playback
+                .engine()
+                .board()
+                .is_opened(&Coordinates::new(4, 4))
+                .unwrap()
+        };
+
+        // The cursor move comes along with the reveal after it.
+        assert!(playback.step_forward().unwrap());
+        assert_eq!(playback.position(), 2);
+        assert!(opened(&playback));
+        assert!(playback.step_forward().unwrap());
+        assert!(playback.step_forward().unwrap());
+        assert!(playback.is_at_end());
+        assert_eq!(playback.engine().state(), &GameState::HitMine);
+        assert!(!playback.step_forward().unwrap());
+
+        assert!(playback.step_back().unwrap());
+        assert_eq!(playback.position(), 3);
+        assert_eq!(playback.engine().state(), &GameState::Playing);
+        assert!(playback
+            .engine()
+            .board()
+            .have_flag(&Coordinates::new(0, 0))
+            .unwrap());
+
+        playback.seek(0).unwrap();
+        assert!(!opened(&playback));
+        assert_eq!(playback.cursor(), None);
+        assert!(!playback.step_back().unwrap());
+
+        let end = playback.duration();
+        playback.seek_time(end).unwrap();
+        assert!(playback.is_at_end());
+        playback.seek_time(Duration::ZERO).unwrap();
+        assert!(playback.position() <= 4);
+        assert_eq!(playback.cursor().is_some(), playback.position() > 0);
+    }
+
+    #[test]
+    fn invalid_events() {
+        assert_eq!(
+            "reveal\t1\t2".parse(),
+            Ok(ReplayAction::Reveal(Coordinates::new(1, 2)))
+        );
+        assert_eq!("undo".parse(), Ok(ReplayAction::Undo));
+        assert_eq!("flag\t1".parse::<ReplayAction>(), Err(()));
+        assert_eq!("undo\t1".parse::<ReplayAction>(), Err(()));
+
+        // Cells off the board are rejected when the replay is read.
+        let (engine, recorder) = recorded_game();
+        let text = recorder.finish(&engine).unwrap().unwrap().to_string();
+        let line = text.lines().count() + 1;
+        for action in ["reveal\t9\t0", "move\t0\t9"] {
+            assert!(matches!(
+                Replay::parse(&format!("{}0\t{}\n", text, action)),
+                Err(ReplayError::InvalidEvent(l)) if l == line
+            ));
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::boardtext::{board_to_text, parse_board};
+use crate::engine::{Clicks, Engine, GameOptions};
+use crate::mineboard::BoardError;
+use crate::utils::write_atomically;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const HEADER: &str = "terminal_minesweeper save";
+
+/// Version written by [`to_string`]. Bump it when the format changes and
+/// keep reading the older versions in [`from_str`].
+pub const SAVE_VERSION: u32 = 2;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    NotASave,
+    UnsupportedVersion(u32),
+    MissingKey(&'static str),
+    InvalidValue { key: String, value: String },
+    Board(BoardError),
+}
+
+impl Error for SaveError {}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{}", e),
+            SaveError::NotASave => write!(f, "not a saved game"),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "saved game version {} is newer than the supported version {}",
+                version, SAVE_VERSION
+            ),
+            SaveError::MissingKey(key) => write!(f, "saved game has no '{}'", key),
+            SaveError::InvalidValue { key, value } => {
+                write!(f, "saved game has an invalid {} '{}'", key, value)
+            }
+            SaveError::Board(e) => write!(f, "saved board: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<BoardError> for SaveError {
+    fn from(e: BoardError) -> Self {
+        SaveError::Board(e)
+    }
+}
+
+/// Where the game in progress is kept between runs.
+pub fn default_path() -> Option<PathBuf> {
+    Some(crate::utils::data_dir()?.join("save.txt"))
+}
+
+/// Writes the full state of a game in progress: options, seed, elapsed
+/// time and the board with its landmines, opened cells and flags.
+pub fn to_string(engine: &Engine) -> Result<String, BoardError> {
+    let options = engine.options();
+    let mut text = format!("{}\nversion={}\n", HEADER, SAVE_VERSION);
+    text += &format!("landmines={}\n", engine.landmine_count());
+    text += &format!("placed={}\n", engine.landmines_placed());
+    text += &format!("seed={}\n", engine.seed());
+    text += &format!("elapsed_ms={}\n", engine.elapsed().as_millis());
+    text += &format!("flag_win={}\n", options.flag_win);
+    text += &format!("first_click={}\n", options.first_click);
+    text += &format!("generator={}\n", options.generator);
+    text += &format!("practice={}\n", options.practice);
+    text += &format!("question_marks={}\n", options.question_marks);
+    text += &format!("hints={}\n", engine.hints_used());
+    let clicks = engine.clicks();
+    text += &format!("left_clicks={}\n", clicks.left);
+    text += &format!("right_clicks={}\n", clicks.right);
+    text += &format!("chord_clicks={}\n", clicks.chord);
+    text += &format!("effective_clicks={}\n", clicks.effective);
+    text += "board\n";
+    text += &board_to_text(engine.board())?;
+    Ok(text)
+}
+
+pub fn from_str(text: &str) -> Result<Engine, SaveError> {
+    let mut lines = text.lines();
+    if lines.next() != Some(HEADER) {
+        return Err(SaveError::NotASave);
+    }
+
+    let mut values = vec![];
+    for line in lines.by_ref() {
+        if line == "board" {
+            break;
+        }
+        match line.split_once('=') {
+            Some((key, value)) => values.push((key, value)),
+            None => return Err(SaveError::NotASave),
+        }
+    }
+    let board: Vec<&str> = lines.collect();
+
+    let get = |key: &'static str| {
+        values
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+            .ok_or(SaveError::MissingKey(key))
+    };
+    fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, SaveError> {
+        value.parse().map_err(|_| SaveError::InvalidValue {
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+    }
+
+    // Keys added after version 1 are missing from older saves.
+    fn optional<T: std::str::FromStr + Default>(
+        key: &str,
+        value: Result<&str, SaveError>,
+    ) -> Result<T, SaveError> {
+        match value {
+            Ok(value) => parse(key, value),
+            Err(_) => Ok(T::default()),
+        }
+    }
+
+    let version: u32 = parse("version", get("version")?)?;
+    if version > SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+
+    let options = GameOptions {
+        flag_win: parse("flag_win", get("flag_win")?)?,
+        first_click: parse("first_click", get("first_click")?)?,
+        generator: parse("generator", get("generator")?)?,
+        seed: Some(parse("seed", get("seed")?)?),
+        practice: optional("practice", get("practice"))?,
+        question_marks: optional("question_marks", get("question_marks"))?,
+    };
+    let landmine_count = parse("landmines", get("landmines")?)?;
+    let placed = parse("placed", get("placed")?)?;
+    let elapsed = Duration::from_millis(parse("elapsed_ms", get("elapsed_ms")?)?);
+    let board = parse_board(&board.join("\n"))?;
+    if placed && *board.landmine_count() != landmine_count {
+        return Err(SaveError::InvalidValue {
+            key: "landmines".to_string(),
+            value: landmine_count.to_string(),
+        });
+    }
+
+    let clicks = Clicks {
+        left: optional("left_clicks", get("left_clicks"))?,
+        right: optional("right_clicks", get("right_clicks"))?,
+        chord: optional("chord_clicks", get("chord_clicks"))?,
+        effective: optional("effective_clicks", get("effective_clicks"))?,
+    };
+
+    Ok(Engine::restore(
+        board,
+        landmine_count,
+        options,
+        placed,
+        elapsed,
+        optional("hints", get("hints"))?,
+        clicks,
+    ))
+}
+
+pub fn save(engine: &Engine, path: &Path) -> Result<(), SaveError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_atomically(path, &to_string(engine)?)?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<Engine, SaveError> {
+    from_str(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::test::{find_cell, test_engine_with};
+    use crate::engine::FirstClick;
+    use crate::generator::Generator;
+    use crate::utils::{Coordinates, Seed};
+
+    #[test]
+    fn round_trip() {
+        let options = GameOptions {
+            flag_win: true,
+            first_click: FirstClick::Opening,
+            generator: Generator::NoGuess { attempts: 50 },
+            seed: Some(Seed(1234)),
+            practice: true,
+            question_marks: true,
+        };
+        let mut engine = test_engine_with(9, 9, 10, options);
+        engine.reveal(&Coordinates::new(4, 4)).unwrap();
+        engine.toggle_flag(&Coordinates::new(0, 0)).unwrap();
+        let unsure = find_cell(&engine, |board, pos| {
+            !board.is_opened(pos).unwrap() && !board.have_flag(pos).unwrap()
+        });
+        engine.toggle_flag(&unsure).unwrap();
+        engine.toggle_flag(&unsure).unwrap();
+
+        let text = to_string(&engine).unwrap();
+        let restored = from_str(&text).unwrap();
+        assert_eq!(restored.seed(), &Seed(1234));
+        assert_eq!(restored.landmine_count(), &10);
+        assert!(restored.landmines_placed());
+        assert_eq!(restored.options().first_click, FirstClick::Opening);
+        assert!(restored.is_practice());
+        assert_eq!(restored.clicks(), engine.clicks());
+        assert_eq!(
+            restored.board().mark(&unsure).unwrap(),
+            crate::mineboard::Mark::Question
+        );
+        let without_time = |text: &str| {
+            text.lines()
+                .filter(|line| !line.starts_with("elapsed_ms="))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        assert_eq!(
+            without_time(&to_string(&restored).unwrap()),
+            without_time(&text)
+        );
+    }
+
+    #[test]
+    fn version_1_saves_load() {
+        let text = "terminal_minesweeper save\n\
+            version=1\n\
+            landmines=2\n\
+            placed=true\n\
+            seed=abc\n\
+            elapsed_ms=12000\n\
+            flag_win=false\n\
+            first_click=opening\n\
+            generator=random\n\
+            board\n\
+            *oo\n\
+            oof\n\
+            ..*\n";
+        let engine = from_str(text).unwrap();
+        assert_eq!(engine.seed(), &"abc".parse::<Seed>().unwrap());
+        assert_eq!(engine.landmine_count(), &2);
+        assert_eq!(engine.options().first_click, FirstClick::Opening);
+        assert!(!engine.is_practice());
+        assert!(!engine.options().question_marks);
+        assert_eq!(engine.hints_used(), 0);
+        assert_eq!(engine.clicks(), &Clicks::default());
+        assert_eq!(*engine.board().opened_count(), 4);
+        assert!(engine.board().have_flag(&Coordinates::new(2, 1)).unwrap());
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let text = format!("{}\nversion={}\nboard\n.\n", HEADER, SAVE_VERSION + 1);
+        assert!(matches!(
+            from_str(&text),
+            Err(SaveError::UnsupportedVersion(v)) if v == SAVE_VERSION + 1
+        ));
+        assert!(matches!(from_str("hello"), Err(SaveError::NotASave)));
+    }
+}
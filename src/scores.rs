@@ -0,0 +1,429 @@
+use crate::difficulty::Difficulty;
+use crate::engine::{Clicks, Efficiency, Engine};
+use crate::utils::{write_atomically, FileLock, Seed};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const HEADER: &str = "terminal_minesweeper scores";
+
+/// Version written by [`ScoreTable::to_string`].
+pub const SCORES_VERSION: u32 = 2;
+
+/// Scores kept for each board.
+pub const MAX_SCORES: usize = 10;
+
+#[derive(Debug)]
+pub enum ScoreError {
+    Io(std::io::Error),
+    NotAScoreTable,
+    UnsupportedVersion(u32),
+    InvalidLine(usize),
+    Locked,
+}
+
+impl Error for ScoreError {}
+
+impl fmt::Display for ScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoreError::Io(e) => write!(f, "{}", e),
+            ScoreError::NotAScoreTable => write!(f, "not a high score table"),
+            ScoreError::UnsupportedVersion(version) => write!(
+                f,
+                "high score version {} is newer than the supported version {}",
+                version, SCORES_VERSION
+            ),
+            ScoreError::InvalidLine(line) => write!(f, "high scores line {} is invalid", line),
+            ScoreError::Locked => write!(f, "high scores are being written by another game"),
+        }
+    }
+}
+
+impl From<std::io::Error> for ScoreError {
+    fn from(e: std::io::Error) -> Self {
+        ScoreError::Io(e)
+    }
+}
+
+/// The board a score was set on. Boards with the same size and landmine
+/// count share a table, whether picked as a preset or as custom.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoardKey {
+    pub width: usize,
+    pub height: usize,
+    pub landmines: usize,
+}
+
+impl From<&Difficulty> for BoardKey {
+    fn from(difficulty: &Difficulty) -> Self {
+        Self {
+            width: difficulty.width(),
+            height: difficulty.height(),
+            landmines: difficulty.landmines(),
+        }
+    }
+}
+
+impl From<&Engine> for BoardKey {
+    fn from(engine: &Engine) -> Self {
+        let config = engine.board().get_config();
+        Self {
+            width: config.width,
+            height: config.height,
+            landmines: *engine.landmine_count(),
+        }
+    }
+}
+
+impl fmt::Display for BoardKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}:{}", self.width, self.height, self.landmines)
+    }
+}
+
+impl std::str::FromStr for BoardKey {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (size, landmines) = s.split_once(':').ok_or(())?;
+        let (width, height) = size.split_once('x').ok_or(())?;
+        Ok(Self {
+            width: width.parse().map_err(|_| ())?,
+            height: height.parse().map_err(|_| ())?,
+            landmines: landmines.parse().map_err(|_| ())?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Score {
+    pub time: Duration,
+    /// Seconds since the Unix epoch.
+    pub date: u64,
+    pub name: String,
+    pub seed: Seed,
+    pub three_bv: usize,
+    /// All zero for scores set before clicks were counted.
+    pub clicks: Clicks,
+}
+
+impl Score {
+    /// A score set now by a game played as `efficiency` says.
+    pub fn new(name: &str, seed: Seed, efficiency: &Efficiency) -> Self {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0);
+        Self {
+            time: efficiency.time,
+            date,
+            name: clean_name(name),
+            seed,
+            three_bv: efficiency.three_bv,
+            clicks: efficiency.clicks,
+        }
+    }
+
+    pub fn efficiency(&self) -> Efficiency {
+        Efficiency {
+            three_bv: self.three_bv,
+            clicks: self.clicks,
+            time: self.time,
+        }
+    }
+}
+
+/// Names are stored as the last field of a line, so they may not hold
+/// tabs or line breaks.
+fn clean_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    match name.trim() {
+        "" => "anonymous".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Best times for every board, fastest first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScoreTable {
+    tables: BTreeMap<BoardKey, Vec<Score>>,
+}
+
+impl ScoreTable {
+    pub fn scores(&self, key: &BoardKey) -> &[Score] {
+        self.tables
+            .get(key)
+            .map(|scores| &scores[..])
+            .unwrap_or(&[])
+    }
+
+    /// Boards that have at least one score.
+    pub fn boards(&self) -> impl Iterator<Item = &BoardKey> {
+        self.tables.keys()
+    }
+
+    /// True when a win in `time` would make it into the table.
+    pub fn qualifies(&self, key: &BoardKey, time: Duration) -> bool {
+        let scores = self.scores(key);
+        scores.len() < MAX_SCORES || scores.last().is_some_and(|last| time < last.time)
+    }
+
+    /// Adds `score` and drops the slowest past [`MAX_SCORES`]. Returns the
+    /// place it took, counted from 0, or `None` when it was too slow.
+    pub fn insert(&mut self, key: BoardKey, score: Score) -> Option<usize> {
+        let scores = self.tables.entry(key).or_default();
+        // Ties go to the score that was set first.
+        let rank = scores.partition_point(|other| other.time <= score.time);
+        scores.insert(rank, score);
+        scores.truncate(MAX_SCORES);
+        (rank < MAX_SCORES).then_some(rank)
+    }
+
+    /// Forgets every score of `key`.
+    pub fn clear(&mut self, key: &BoardKey) {
+        self.tables.remove(key);
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ScoreError> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(ScoreError::NotAScoreTable);
+        }
+        let version = match lines.next() {
+            Some((_, line)) => line
+                .strip_prefix("version=")
+                .and_then(|version| version.parse().ok())
+                .ok_or(ScoreError::NotAScoreTable)?,
+            None => return Err(ScoreError::NotAScoreTable),
+        };
+        if version > SCORES_VERSION {
+            return Err(ScoreError::UnsupportedVersion(version));
+        }
+
+        let mut table = ScoreTable::default();
+        for (i, line) in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let (key, score) = parse_score(line, version).ok_or(ScoreError::InvalidLine(i + 1))?;
+            table.insert(key, score);
+        }
+        Ok(table)
+    }
+}
+
+/// Reads a score line. Version 1 lines have no clicks.
+fn parse_score(line: &str, version: u32) -> Option<(BoardKey, Score)> {
+    let count = if version < 2 { 6 } else { 10 };
+    let mut fields = line.splitn(count, '\t');
+    let key = fields.next()?.parse().ok()?;
+    let time = Duration::from_millis(fields.next()?.parse().ok()?);
+    let date = fields.next()?.parse().ok()?;
+    let seed = fields.next()?.parse().ok()?;
+    let three_bv = fields.next()?.parse().ok()?;
+    let mut clicks = Clicks::default();
+    if version >= 2 {
+        clicks.left = fields.next()?.parse().ok()?;
+        clicks.right = fields.next()?.parse().ok()?;
+        clicks.chord = fields.next()?.parse().ok()?;
+        clicks.effective = fields.next()?.parse().ok()?;
+    }
+    let name = fields.next()?.to_string();
+    Some((
+        key,
+        Score {
+            time,
+            date,
+            name,
+            seed,
+            three_bv,
+            clicks,
+        },
+    ))
+}
+
+impl fmt::Display for ScoreTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}\nversion={}", HEADER, SCORES_VERSION)?;
+        for (key, scores) in &self.tables {
+            for score in scores {
+                writeln!(
+                    f,
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    key,
+                    score.time.as_millis(),
+                    score.date,
+                    score.seed,
+                    score.three_bv,
+                    score.clicks.left,
+                    score.clicks.right,
+                    score.clicks.chord,
+                    score.clicks.effective,
+                    score.name
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where the high scores are kept.
+pub fn default_path() -> Option<PathBuf> {
+    Some(crate::utils::data_dir()?.join("scores.txt"))
+}
+
+/// Reads the scores at `path`, starting empty when there is no file yet.
+pub fn load(path: &Path) -> Result<ScoreTable, ScoreError> {
+    match fs::read_to_string(path) {
+        Ok(text) => ScoreTable::parse(&text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ScoreTable::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Changes the scores at `path` with `update`, holding a lock file so that
+/// two games finishing at once both get their score in. The new table is
+/// written to a temporary file and renamed over the old one, so a reader
+/// never sees half of it.
+pub fn update<T>(path: &Path, update: impl FnOnce(&mut ScoreTable) -> T) -> Result<T, ScoreError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let _lock = FileLock::acquire(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::TimedOut => ScoreError::Locked,
+        _ => ScoreError::Io(e),
+    })?;
+    let mut table = load(path)?;
+    let result = update(&mut table);
+    write_atomically(path, &table.to_string())?;
+    Ok(result)
+}
+
+/// Adds `score` to the table at `path`. Returns its place, counted from 0.
+pub fn record(path: &Path, key: BoardKey, score: Score) -> Result<Option<usize>, ScoreError> {
+    update(path, |table| table.insert(key, score))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    const KEY: BoardKey = BoardKey {
+        width: 9,
+        height: 9,
+        landmines: 10,
+    };
+
+    fn score(millis: u64, name: &str) -> Score {
+        Score {
+            time: Duration::from_millis(millis),
+            date: 1_700_000_000,
+            name: name.to_string(),
+            seed: Seed(42),
+            three_bv: 17,
+            clicks: Clicks {
+                left: 12,
+                right: 3,
+                chord: 5,
+                effective: 18,
+            },
+        }
+    }
+
+    #[test]
+    fn table_keeps_the_fastest() {
+        let mut table = ScoreTable::default();
+        for i in 0..MAX_SCORES as u64 {
+            assert_eq!(
+                table.insert(KEY, score(10_000 + i, "slow")),
+                Some(i as usize)
+            );
+        }
+        assert!(!table.qualifies(&KEY, Duration::from_millis(20_000)));
+        assert!(table.qualifies(&KEY, Duration::from_millis(5_000)));
+
+        assert_eq!(table.insert(KEY, score(5_000, "fast")), Some(0));
+        assert_eq!(table.insert(KEY, score(10_000, "tie")), Some(2));
+        assert_eq!(table.insert(KEY, score(30_000, "slowest")), None);
+        let scores = table.scores(&KEY);
+        assert_eq!(scores.len(), MAX_SCORES);
+        assert_eq!(scores[0].name, "fast");
+        assert_eq!(scores[2].name, "tie");
+        assert!(table
+            .scores(&BoardKey::from(&Difficulty::Expert))
+            .is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut table = ScoreTable::default();
+        table.insert(KEY, score(12_345, "kiana kaslana"));
+        table.insert(BoardKey::from(&Difficulty::Expert), score(99_000, "mei"));
+
+        let parsed = ScoreTable::parse(&table.to_string()).unwrap();
+        assert_eq!(parsed, table);
+        assert!(matches!(
+            ScoreTable::parse(&format!("{}\nversion=2\n9x9:10\t1\n", HEADER)),
+            Err(ScoreError::InvalidLine(3))
+        ));
+        assert!(matches!(
+            ScoreTable::parse("hello"),
+            Err(ScoreError::NotAScoreTable)
+        ));
+    }
+
+    #[test]
+    fn version_1_scores_have_no_clicks() {
+        let text = format!(
+            "{}\nversion=1\n9x9:10\t12345\t1700000000\t42\t17\tmei\n",
+            HEADER
+        );
+        let table = ScoreTable::parse(&text).unwrap();
+        let score = &table.scores(&KEY)[0];
+        assert_eq!(score.name, "mei");
+        assert_eq!(score.three_bv, 17);
+        assert_eq!(score.clicks, Clicks::default());
+        assert_eq!(score.efficiency().ioe(), None);
+        assert!(table.to_string().contains("version=2\n"));
+    }
+
+    #[test]
+    fn names_fit_on_one_line() {
+        let efficiency = Efficiency {
+            three_bv: 1,
+            clicks: Clicks::default(),
+            time: Duration::ZERO,
+        };
+        let score = Score::new("a\tb\nc", Seed(1), &efficiency);
+        assert_eq!(score.name, "a b c");
+        assert_eq!(Score::new("  ", Seed(1), &efficiency).name, "anonymous");
+    }
+
+    #[test]
+    fn concurrent_records_are_all_kept() {
+        let dir = std::env::temp_dir().join(format!("minesweeper-scores-{}", std::process::id()));
+        let path = dir.join("scores.txt");
+        let _ = fs::remove_dir_all(&dir);
+
+        let writers: Vec<_> = (0..4)
+            .map(|i| {
+                let path = path.clone();
+                thread::spawn(move || record(&path, KEY, score(1_000 * (i + 1), "player")))
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap().unwrap();
+        }
+
+        assert_eq!(load(&path).unwrap().scores(&KEY).len(), 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+#![cfg(feature = "bracketed-paste")]
+use crate::engine::Engine;
+use crate::menu::items::{Button, Lable, MenuItem, TextBox};
+use crate::menu::Menu;
+use crate::scores::{self, BoardKey, Score, ScoreTable};
+use crate::stats::{self, Stats};
+use crate::utils::format_date;
+
+const NAME: &str = "Name";
+const RESET: &str = "Reset statistics";
+
+fn lables(lines: Vec<String>) -> Menu {
+    Menu::new(
+        lines
+            .iter()
+            .map(|line| MenuItem::Lable(Lable::new(line)))
+            .collect(),
+    )
+}
+
+/// A ratio with two decimals, or a dash when there is none.
+fn rate(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |value| format!("{:.2}", value))
+}
+
+/// One row per score, marking the one at `highlight`.
+fn score_lines(scores: &[Score], highlight: Option<usize>) -> Vec<String> {
+    if scores.is_empty() {
+        return vec!["No scores yet".to_string()];
+    }
+    let mut lines = vec![format!(
+        "   {:>3}  {:>8}  {:<16}  {:<10}  {:>4}  {:>5}  {:>4}  Seed",
+        "#", "Time", "Name", "Date", "3BV", "3BV/s", "IOE"
+    )];
+    for (i, score) in scores.iter().enumerate() {
+        let marker = if highlight == Some(i) { "*" } else { " " };
+        let efficiency = score.efficiency();
+        lines.push(format!(
+            "{}  {:>3}  {:>7.3}s  {:<16}  {:<10}  {:>4}  {:>5}  {:>4}  {}",
+            marker,
+            i + 1,
+            score.time.as_secs_f64(),
+            score.name.chars().take(16).collect::<String>(),
+            format_date(score.date),
+            score.three_bv,
+            rate(efficiency.three_bv_per_second()),
+            rate(efficiency.ioe()),
+            score.seed
+        ));
+    }
+    lines
+}
+
+/// Shows the high scores of `key` until a key is pressed.
+pub fn show_scores(key: &BoardKey, highlight: Option<usize>, notice: Option<&str>) {
+    let table = scores::default_path()
+        .map(|path| scores::load(&path))
+        .unwrap_or_else(|| Ok(ScoreTable::default()));
+    let mut lines = vec![format!("High scores for {}", key), String::new()];
+    match &table {
+        Ok(table) => lines.extend(score_lines(table.scores(key), highlight)),
+        Err(e) => lines.push(format!("Could not read the high scores: {}", e)),
+    }
+    lines.push(String::new());
+    if let Some(notice) = notice {
+        lines.push(notice.to_string());
+    }
+    lines.push("Enter/Esc: back".to_string());
+    lables(lines).run();
+}
+
+/// Offers a place in the high scores to a won game, asking for the name
+/// to keep it under.
+pub fn record_win(engine: &Engine) {
+    let Some(path) = scores::default_path() else {
+        return;
+    };
+    let key = BoardKey::from(engine);
+    let time = engine.elapsed();
+    let qualifies = scores::load(&path)
+        .map(|table| table.qualifies(&key, time))
+        .unwrap_or(true);
+    if !qualifies {
+        return;
+    }
+
+    let default_name = std::env::var("USER").unwrap_or_default();
+    let mut menu = Menu::new(vec![
+        MenuItem::Lable(Lable::new(&format!(
+            "New high score on {}: {:.3}s",
+            key,
+            time.as_secs_f64()
+        ))),
+        MenuItem::Lable(Lable::new("")),
+        MenuItem::TextBox(TextBox::new(NAME, NAME, &default_name).max_length(16)),
+        MenuItem::Lable(Lable::new("")),
+        MenuItem::Lable(Lable::new("Enter: save  Esc: skip")),
+    ]);
+    let Some(result) = menu.run() else {
+        return;
+    };
+    let name = result.get_text(NAME).unwrap_or_default();
+    let Ok(efficiency) = engine.efficiency() else {
+        return;
+    };
+    let score = Score::new(name, *engine.seed(), &efficiency);
+
+    match scores::record(&path, key, score) {
+        Ok(rank) => show_scores(&key, rank, None),
+        Err(e) => show_scores(
+            &key,
+            None,
+            Some(&format!("Could not save the score: {}", e)),
+        ),
+    }
+}
+
+fn stats_lines(stats: &Stats) -> Vec<String> {
+    let percent = |rate: Option<f64>| match rate {
+        Some(rate) => format!("{:.1}%", rate * 100.0),
+        None => "-".to_string(),
+    };
+    let average = match stats.average_time() {
+        Some(time) => format!("{:.3}s", time.as_secs_f64()),
+        None => "-".to_string(),
+    };
+    vec![
+        format!("Games played:    {}", stats.played),
+        format!("Won:             {}", stats.won),
+        format!("Lost:            {}", stats.lost),
+        format!("Win rate:        {}", percent(stats.win_rate())),
+        format!("Current streak:  {}", stats.current_streak),
+        format!("Longest streak:  {}", stats.longest_streak),
+        format!("Average time:    {}", average),
+        format!("Cells revealed:  {}", stats.cells_revealed),
+    ]
+}
+
+/// Shows the lifetime statistics of `key`, offering to reset them.
+pub fn show_stats(key: &BoardKey) {
+    let Some(path) = stats::default_path() else {
+        return;
+    };
+    let mut notice: Option<String> = None;
+    loop {
+        let mut lines = vec![format!("Statistics for {}", key), String::new()];
+        match stats::load(&path) {
+            Ok(table) => lines.extend(stats_lines(&table.get(key))),
+            Err(e) => lines.push(format!("Could not read the statistics: {}", e)),
+        }
+        lines.push(String::new());
+        let mut items: Vec<MenuItem> = lines
+            .iter()
+            .map(|line| MenuItem::Lable(Lable::new(line)))
+            .collect();
+        items.push(MenuItem::Button(Button::new(RESET, "Reset")));
+        items.push(MenuItem::Lable(Lable::new("")));
+        if let Some(notice) = notice.take() {
+            items.push(MenuItem::Lable(Lable::new(&notice)));
+        }
+        items.push(MenuItem::Lable(Lable::new("Enter/Esc: back")));
+
+        match Menu::new(items).run() {
+            Some(result) if result.is_pressed(RESET) => {
+                if confirm(&format!("Reset the statistics for {}?", key)) {
+                    if let Err(e) = stats::update(&path, |table| table.reset(key)) {
+                        notice = Some(format!("Could not reset the statistics: {}", e));
+                    }
+                }
+            }
+            _ => return,
+        }
+    }
+}
+
+/// Asks a yes or no question.
+fn confirm(question: &str) -> bool {
+    lables(vec![
+        question.to_string(),
+        String::new(),
+        "Enter: yes  Esc: no".to_string(),
+    ])
+    .run()
+    .is_some()
+}
@@ -0,0 +1,496 @@
+use crate::difficulty::Difficulty;
+use crate::mineboard::{Board, BoardConfig, Symbols};
+use crate::utils::ParseOptionError;
+use crossterm::style::Color;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use unicode_width::UnicodeWidthStr;
+
+const HEADER: &str = "# terminal_minesweeper settings";
+
+/// Colours that can be named in the settings file. Any other colour is
+/// written as `#rrggbb`.
+const COLOURS: [(&str, Color); 17] = [
+    ("reset", Color::Reset),
+    ("black", Color::Black),
+    ("dark_grey", Color::DarkGrey),
+    ("red", Color::Red),
+    ("dark_red", Color::DarkRed),
+    ("green", Color::Green),
+    ("dark_green", Color::DarkGreen),
+    ("yellow", Color::Yellow),
+    ("dark_yellow", Color::DarkYellow),
+    ("blue", Color::Blue),
+    ("dark_blue", Color::DarkBlue),
+    ("magenta", Color::Magenta),
+    ("dark_magenta", Color::DarkMagenta),
+    ("cyan", Color::Cyan),
+    ("dark_cyan", Color::DarkCyan),
+    ("white", Color::White),
+    ("grey", Color::Grey),
+];
+
+pub fn parse_colour(s: &str) -> Result<Color, ParseOptionError> {
+    if let Some((_, colour)) = COLOURS.iter().find(|(name, _)| *name == s) {
+        return Ok(*colour);
+    }
+    let error = || ParseOptionError(s.to_string());
+    let hex = s
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .ok_or_else(error)?;
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+    };
+    match (channel(0), channel(2), channel(4)) {
+        (Some(r), Some(g), Some(b)) => Ok(Color::Rgb { r, g, b }),
+        _ => Err(error()),
+    }
+}
+
+pub fn colour_name(colour: Color) -> String {
+    match COLOURS.iter().find(|(_, c)| *c == colour) {
+        Some((name, _)) => name.to_string(),
+        None => match colour {
+            Color::Rgb { r, g, b } => format!("#{:02x}{:02x}{:02x}", r, g, b),
+            _ => "reset".to_string(),
+        },
+    }
+}
+
+/// Something a key can be bound to in a game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Reveal,
+    Flag,
+    /// Points out a cell to play next.
+    Hint,
+    /// Shows the chance of a landmine on every closed cell.
+    Analysis,
+    /// Takes back the last move in practice mode.
+    Undo,
+    Redo,
+    Quit,
+}
+
+/// Letters for the game keys. The arrow keys and Enter always work as
+/// well.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    pub up: char,
+    pub down: char,
+    pub left: char,
+    pub right: char,
+    pub reveal: char,
+    pub flag: char,
+    pub hint: char,
+    pub analysis: char,
+    pub undo: char,
+    pub redo: char,
+    pub quit: char,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            up: 'w',
+            down: 's',
+            left: 'a',
+            right: 'd',
+            reveal: ' ',
+            flag: 'f',
+            hint: 'h',
+            analysis: 'p',
+            undo: 'u',
+            redo: 'r',
+            quit: 'q',
+        }
+    }
+}
+
+impl KeyBindings {
+    fn bindings(&self) -> [(KeyAction, char); 11] {
+        [
+            (KeyAction::Up, self.up),
+            (KeyAction::Down, self.down),
+            (KeyAction::Left, self.left),
+            (KeyAction::Right, self.right),
+            (KeyAction::Reveal, self.reveal),
+            (KeyAction::Flag, self.flag),
+            (KeyAction::Hint, self.hint),
+            (KeyAction::Analysis, self.analysis),
+            (KeyAction::Undo, self.undo),
+            (KeyAction::Redo, self.redo),
+            (KeyAction::Quit, self.quit),
+        ]
+    }
+
+    /// What `c` is bound to, ignoring case.
+    pub fn action(&self, c: char) -> Option<KeyAction> {
+        let c = c.to_ascii_lowercase();
+        self.bindings()
+            .iter()
+            .find(|(_, key)| *key == c)
+            .map(|(action, _)| *action)
+    }
+
+    /// A key bound to more than one action, if any.
+    pub fn duplicate(&self) -> Option<char> {
+        let bindings = self.bindings();
+        bindings
+            .iter()
+            .enumerate()
+            .find(|(i, (_, key))| bindings[i + 1..].iter().any(|(_, other)| other == key))
+            .map(|(_, (_, key))| *key)
+    }
+}
+
+fn parse_key(s: &str) -> Option<char> {
+    if s == "space" {
+        return Some(' ');
+    }
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_control() => Some(c.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+/// How a key is written in the settings file.
+pub fn key_name(c: char) -> String {
+    match c {
+        ' ' => "space".to_string(),
+        _ => c.to_string(),
+    }
+}
+
+/// A symbol has to fill a cell exactly, or the cursor and the mouse would
+/// no longer line up with the board.
+fn parse_symbol(s: &str) -> Option<String> {
+    let s = match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(quoted) => quoted,
+        None => s,
+    };
+    let cell_width = BoardConfig::unicode(1, 1, Color::Reset).char_width as usize;
+    (s.width() == cell_width).then(|| s.to_string())
+}
+
+/// A line of the settings file that was skipped.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingsWarning {
+    Malformed {
+        line: usize,
+    },
+    UnknownKey {
+        line: usize,
+        key: String,
+    },
+    InvalidValue {
+        line: usize,
+        key: String,
+        value: String,
+    },
+    DuplicateKey {
+        binding: char,
+    },
+}
+
+impl fmt::Display for SettingsWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsWarning::Malformed { line } => {
+                write!(f, "settings line {}: expected key=value", line)
+            }
+            SettingsWarning::UnknownKey { line, key } => {
+                write!(f, "settings line {}: unknown key '{}'", line, key)
+            }
+            SettingsWarning::InvalidValue { line, key, value } => write!(
+                f,
+                "settings line {}: invalid {} '{}', using the default",
+                line, key, value
+            ),
+            SettingsWarning::DuplicateKey { binding } => write!(
+                f,
+                "settings: '{}' is bound to more than one action, using the default keys",
+                key_name(*binding)
+            ),
+        }
+    }
+}
+
+/// Why [`Settings::set`] refused a value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SetError {
+    UnknownKey,
+    InvalidValue,
+}
+
+/// Everything that can be changed between runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Selected in the setup menu at startup.
+    pub difficulty: Difficulty,
+    pub background: Color,
+    pub numbers: Color,
+    pub symbols: Symbols,
+    pub keys: KeyBindings,
+    /// Whether the flag key also puts question marks on cells.
+    pub question_marks: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::default(),
+            background: Color::Reset,
+            numbers: Color::Reset,
+            symbols: Symbols::default(),
+            keys: KeyBindings::default(),
+            question_marks: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads a settings file. Lines that cannot be used are reported and the
+    /// defaults are kept for them.
+    pub fn parse(text: &str) -> (Self, Vec<SettingsWarning>) {
+        let mut settings = Settings::default();
+        let mut warnings = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warnings.push(SettingsWarning::Malformed { line: line_number });
+                continue;
+            };
+            let key = key.trim();
+            match settings.set(key, value.trim_start()) {
+                Ok(()) => (),
+                Err(SetError::UnknownKey) => warnings.push(SettingsWarning::UnknownKey {
+                    line: line_number,
+                    key: key.to_string(),
+                }),
+                Err(SetError::InvalidValue) => warnings.push(SettingsWarning::InvalidValue {
+                    line: line_number,
+                    key: key.to_string(),
+                    value: value.to_string(),
+                }),
+            }
+        }
+        // Two actions on one key would leave one of them out of reach.
+        if let Some(binding) = settings.keys.duplicate() {
+            warnings.push(SettingsWarning::DuplicateKey { binding });
+            settings.keys = KeyBindings::default();
+        }
+        (settings, warnings)
+    }
+
+    /// Every key with its current value, in the order they are written.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let quoted = |symbol: &str| format!("\"{}\"", symbol);
+        vec![
+            ("difficulty", self.difficulty.key()),
+            ("question_marks", self.question_marks.to_string()),
+            ("colour.background", colour_name(self.background)),
+            ("colour.numbers", colour_name(self.numbers)),
+            ("symbol.landmine", quoted(&self.symbols.landmine)),
+            ("symbol.exploded", quoted(&self.symbols.exploded)),
+            ("symbol.flag", quoted(&self.symbols.flag)),
+            ("symbol.wrong_flag", quoted(&self.symbols.wrong_flag)),
+            ("symbol.question", quoted(&self.symbols.question)),
+            ("symbol.closed", quoted(&self.symbols.closed)),
+            ("symbol.opened", quoted(&self.symbols.opened)),
+            ("key.up", key_name(self.keys.up)),
+            ("key.down", key_name(self.keys.down)),
+            ("key.left", key_name(self.keys.left)),
+            ("key.right", key_name(self.keys.right)),
+            ("key.reveal", key_name(self.keys.reveal)),
+            ("key.flag", key_name(self.keys.flag)),
+            ("key.hint", key_name(self.keys.hint)),
+            ("key.analysis", key_name(self.keys.analysis)),
+            ("key.undo", key_name(self.keys.undo)),
+            ("key.redo", key_name(self.keys.redo)),
+            ("key.quit", key_name(self.keys.quit)),
+        ]
+    }
+
+    /// Changes one setting, given as in the settings file.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), SetError> {
+        let invalid = |_| SetError::InvalidValue;
+        let symbol = |value| parse_symbol(value).ok_or(SetError::InvalidValue);
+        let binding = |value| parse_key(value).ok_or(SetError::InvalidValue);
+        match key {
+            "difficulty" => self.difficulty = value.parse().map_err(invalid)?,
+            "question_marks" => {
+                self.question_marks = value.parse().map_err(|_| SetError::InvalidValue)?
+            }
+            "colour.background" => self.background = parse_colour(value).map_err(invalid)?,
+            "colour.numbers" => self.numbers = parse_colour(value).map_err(invalid)?,
+            "symbol.landmine" => self.symbols.landmine = symbol(value)?,
+            "symbol.exploded" => self.symbols.exploded = symbol(value)?,
+            "symbol.flag" => self.symbols.flag = symbol(value)?,
+            "symbol.wrong_flag" => self.symbols.wrong_flag = symbol(value)?,
+            "symbol.question" => self.symbols.question = symbol(value)?,
+            "symbol.closed" => self.symbols.closed = symbol(value)?,
+            "symbol.opened" => self.symbols.opened = symbol(value)?,
+            "key.up" => self.keys.up = binding(value)?,
+            "key.down" => self.keys.down = binding(value)?,
+            "key.left" => self.keys.left = binding(value)?,
+            "key.right" => self.keys.right = binding(value)?,
+            "key.reveal" => self.keys.reveal = binding(value)?,
+            "key.flag" => self.keys.flag = binding(value)?,
+            "key.hint" => self.keys.hint = binding(value)?,
+            "key.analysis" => self.keys.analysis = binding(value)?,
+            "key.undo" => self.keys.undo = binding(value)?,
+            "key.redo" => self.keys.redo = binding(value)?,
+            "key.quit" => self.keys.quit = binding(value)?,
+            _ => return Err(SetError::UnknownKey),
+        }
+        Ok(())
+    }
+
+    /// The board for `difficulty`, drawn with these symbols and colours.
+    pub fn board_config(&self, difficulty: &Difficulty) -> BoardConfig {
+        let mut config = difficulty
+            .board_config()
+            .with_symbols(self.symbols.clone())
+            .with_number_colour(self.numbers);
+        config.board_background_colour = self.background;
+        config
+    }
+
+    /// Draws an existing board with these symbols and colours.
+    pub fn apply(&self, board: &mut Board) {
+        board.set_style(self.symbols.clone(), self.background, self.numbers);
+    }
+}
+
+impl fmt::Display for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for (key, value) in self.entries() {
+            writeln!(f, "{}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the settings are kept.
+pub fn default_path() -> Option<PathBuf> {
+    Some(crate::utils::config_dir()?.join("settings.txt"))
+}
+
+/// Reads the settings at `path`, using the defaults when there is no file
+/// yet.
+pub fn load(path: &Path) -> std::io::Result<(Settings, Vec<SettingsWarning>)> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Settings::parse(&text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok((Settings::default(), vec![])),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn save(settings: &Settings, path: &Path) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, settings.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut settings = Settings {
+            difficulty: Difficulty::custom(20, 10, 30).unwrap(),
+            background: Color::Rgb { r: 1, g: 2, b: 250 },
+            numbers: Color::DarkCyan,
+            ..Settings::default()
+        };
+        settings.symbols.opened = ". ".to_string();
+        settings.keys.reveal = 'e';
+        settings.question_marks = false;
+
+        let (parsed, warnings) = Settings::parse(&settings.to_string());
+        assert!(warnings.is_empty());
+        assert_eq!(parsed, settings);
+    }
+
+    #[test]
+    fn symbols_fill_a_cell() {
+        assert_eq!(parse_symbol("\"F \""), Some("F ".to_string()));
+        assert_eq!(parse_symbol("🚩"), Some("🚩".to_string()));
+        assert_eq!(parse_symbol("F"), None);
+        assert_eq!(parse_symbol("🚩🚩"), None);
+        assert_eq!(parse_symbol("F🚩"), None);
+    }
+
+    #[test]
+    fn unusable_lines_are_reported() {
+        let text = "# comment\n\nkey.up=k\ncolour=red\nsymbol.flag=toolong\nnonsense\nkey.down=k\n";
+        let (settings, warnings) = Settings::parse(text);
+        assert_eq!(settings.keys, KeyBindings::default());
+        assert_eq!(settings.symbols.flag, Symbols::default().flag);
+        assert_eq!(
+            warnings,
+            vec![
+                SettingsWarning::UnknownKey {
+                    line: 4,
+                    key: "colour".to_string()
+                },
+                SettingsWarning::InvalidValue {
+                    line: 5,
+                    key: "symbol.flag".to_string(),
+                    value: "toolong".to_string()
+                },
+                SettingsWarning::Malformed { line: 6 },
+                SettingsWarning::DuplicateKey { binding: 'k' },
+            ]
+        );
+    }
+
+    #[test]
+    fn key_bindings() {
+        let keys = KeyBindings::default();
+        assert_eq!(keys.action('W'), Some(KeyAction::Up));
+        assert_eq!(keys.action(' '), Some(KeyAction::Reveal));
+        assert_eq!(keys.action('u'), Some(KeyAction::Undo));
+        assert_eq!(keys.action('x'), None);
+        assert_eq!(keys.duplicate(), None);
+    }
+
+    #[test]
+    fn colours() {
+        assert_eq!(parse_colour("dark_red"), Ok(Color::DarkRed));
+        assert_eq!(
+            parse_colour("#ff8000"),
+            Ok(Color::Rgb {
+                r: 255,
+                g: 128,
+                b: 0
+            })
+        );
+        assert!(parse_colour("#ff80").is_err());
+        assert!(parse_colour("purple").is_err());
+        assert_eq!(
+            colour_name(Color::Rgb {
+                r: 255,
+                g: 128,
+                b: 0
+            }),
+            "#ff8000"
+        );
+    }
+}
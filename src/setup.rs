@@ -0,0 +1,243 @@
+#![cfg(feature = "bracketed-paste")]
+use crate::difficulty::Difficulty;
+use crate::engine::GameOptions;
+use crate::menu::items::{Button, Lable, MenuItem, RadioButton, TextBox};
+use crate::menu::{Menu, MenuResult};
+use crate::scores::BoardKey;
+use crate::screens;
+use crate::settings::{self, SetError, Settings};
+use crate::viewer;
+
+const DIFFICULTY: &str = "Difficulty";
+const CUSTOM: &str = "Custom";
+const WIDTH: &str = "Width";
+const HEIGHT: &str = "Height";
+const LANDMINES: &str = "Landmines";
+const MODE: &str = "Mode";
+const NORMAL: &str = "Normal";
+const PRACTICE: &str = "Practice";
+const SETTINGS: &str = "Settings";
+const HIGH_SCORES: &str = "High scores";
+const STATISTICS: &str = "Statistics";
+const LAST_GAME: &str = "Last game";
+
+/// Terminal columns and rows, or a classic 80x24 when they are unknown.
+fn terminal_size() -> (usize, usize) {
+    crossterm::terminal::size()
+        .map(|(columns, rows)| (columns as usize, rows as usize))
+        .unwrap_or((80, 24))
+}
+
+/// A text box for a number between `min` and `max`, starting at `default`
+/// or the nearest number in range.
+fn integer_box(text: &str, default: usize, min: usize, max: usize) -> MenuItem {
+    let max = max.max(min);
+    let default = default.clamp(min, max).to_string();
+    MenuItem::TextBox(TextBox::new(text, text, &default).integer(min as i64, max as i64))
+}
+
+/// The setup menu, starting at the default difficulty of `settings` and
+/// the mode of `game_options`.
+fn setup_menu(settings: &Settings, game_options: &GameOptions) -> Menu {
+    let (max_width, max_height) = {
+        let (columns, rows) = terminal_size();
+        Difficulty::max_size(columns, rows)
+    };
+    let mut options: Vec<String> = Difficulty::PRESETS
+        .iter()
+        .map(|difficulty| difficulty.name().to_string())
+        .collect();
+    options.push(CUSTOM.to_string());
+    let default = &settings.difficulty;
+    let difficulty = RadioButton::new(DIFFICULTY, &options).with_default(default.name());
+    // The custom fields start at the custom default, or at expert size.
+    let custom = match default {
+        Difficulty::Custom { .. } => default,
+        _ => &Difficulty::Expert,
+    };
+    let mode = RadioButton::new(MODE, &[NORMAL.to_string(), PRACTICE.to_string()]).with_default(
+        if game_options.practice {
+            PRACTICE
+        } else {
+            NORMAL
+        },
+    );
+
+    Menu::new(vec![
+        MenuItem::Lable(Lable::new("Terminal Minesweeper")),
+        MenuItem::Lable(Lable::new("")),
+        MenuItem::RadioButton(difficulty),
+        integer_box(WIDTH, custom.width(), 1, max_width),
+        integer_box(HEIGHT, custom.height(), 1, max_height),
+        integer_box(
+            LANDMINES,
+            custom.landmines(),
+            0,
+            (max_width * max_height).saturating_sub(1),
+        ),
+        MenuItem::RadioButton(mode),
+        MenuItem::Button(Button::new(HIGH_SCORES, "Show")),
+        MenuItem::Button(Button::new(STATISTICS, "Show")),
+        MenuItem::Button(Button::new(LAST_GAME, "Watch")),
+        MenuItem::Button(Button::new(SETTINGS, "Open")),
+        MenuItem::Lable(Lable::new("")),
+        MenuItem::Lable(Lable::new(
+            "Up/Down: move  Left/Right: choose  Enter: start  Esc: quit",
+        )),
+        MenuItem::Lable(Lable::new(
+            "Text: Left/Right/Home/End move the cursor, Backspace/Delete erase",
+        )),
+    ])
+}
+
+fn integer_value(result: &MenuResult, text: &str) -> Result<usize, String> {
+    result
+        .get_integer(text)
+        .and_then(|value| usize::try_from(value).ok())
+        .ok_or_else(|| format!("{} must be a whole number", text))
+}
+
+/// Reads the difficulty picked in the setup menu and checks that it fits
+/// in a terminal of `columns` by `rows`.
+fn selected_difficulty(
+    result: &MenuResult,
+    columns: usize,
+    rows: usize,
+) -> Result<Difficulty, String> {
+    let selected = result.get_choice(DIFFICULTY);
+
+    let difficulty = match Difficulty::PRESETS
+        .iter()
+        .find(|difficulty| Some(difficulty.name()) == selected)
+    {
+        Some(preset) => preset.clone(),
+        None => {
+            let width = integer_value(result, WIDTH)?;
+            let height = integer_value(result, HEIGHT)?;
+            let landmines = integer_value(result, LANDMINES)?;
+            Difficulty::custom(width, height, landmines).map_err(|e| e.to_string())?
+        }
+    };
+
+    if !difficulty.fits(columns, rows) {
+        let (needed_columns, needed_rows) = difficulty.terminal_size();
+        return Err(format!(
+            "{} needs a terminal of at least {}x{}, this one is {}x{}",
+            difficulty.name(),
+            needed_columns,
+            needed_rows,
+            columns,
+            rows
+        ));
+    }
+    Ok(difficulty)
+}
+
+/// Asks for the board to play on and whether to practice, which is set in
+/// `options`. Returns `None` when the player quits. `notice` is shown until
+/// the menu is first confirmed.
+pub fn run_setup(
+    settings: &mut Settings,
+    options: &mut GameOptions,
+    notice: Option<&str>,
+) -> Option<Difficulty> {
+    let mut menu = setup_menu(settings, options);
+    if let Some(notice) = notice {
+        menu.set_message(notice);
+    }
+    loop {
+        let result = menu.run()?;
+        if result.is_pressed(SETTINGS) {
+            let message = run_settings(settings);
+            menu = setup_menu(settings, options);
+            if let Some(message) = message {
+                menu.set_message(&message);
+            }
+            continue;
+        }
+        if result.is_pressed(LAST_GAME) {
+            if let Some(message) = viewer::watch_last(settings) {
+                menu.set_message(&message);
+            }
+            continue;
+        }
+        let (columns, rows) = terminal_size();
+        if result.is_pressed(HIGH_SCORES) || result.is_pressed(STATISTICS) {
+            // These can be looked at even for a board too large to play.
+            match selected_difficulty(&result, usize::MAX, usize::MAX) {
+                Ok(difficulty) if result.is_pressed(HIGH_SCORES) => {
+                    screens::show_scores(&BoardKey::from(&difficulty), None, None)
+                }
+                Ok(difficulty) => screens::show_stats(&BoardKey::from(&difficulty)),
+                Err(message) => menu.set_message(&message),
+            }
+            continue;
+        }
+        match selected_difficulty(&result, columns, rows) {
+            Ok(difficulty) => {
+                options.practice = result.get_choice(MODE) == Some(PRACTICE);
+                return Some(difficulty);
+            }
+            Err(message) => menu.set_message(&message),
+        }
+    }
+}
+
+fn settings_menu(settings: &Settings) -> Menu {
+    let mut items = vec![
+        MenuItem::Lable(Lable::new(SETTINGS)),
+        MenuItem::Lable(Lable::new("")),
+    ];
+    for (key, value) in settings.entries() {
+        items.push(MenuItem::TextBox(
+            TextBox::new(key, key, &value).max_length(32),
+        ));
+    }
+    items.extend([
+        MenuItem::Lable(Lable::new("")),
+        MenuItem::Lable(Lable::new(
+            "Symbols are quoted, keys are a letter or 'space', colours a name or #rrggbb",
+        )),
+        MenuItem::Lable(Lable::new(
+            "Difficulty: beginner, intermediate, expert or custom:<width>x<height>:<mines>",
+        )),
+        MenuItem::Lable(Lable::new("Enter: save  Esc: cancel")),
+    ]);
+    Menu::new(items)
+}
+
+/// Reads the values of the settings menu on top of `settings`.
+fn edited_settings(settings: &Settings, result: &MenuResult) -> Result<Settings, String> {
+    let mut edited = settings.clone();
+    for (key, _) in settings.entries() {
+        let value = result.get_text(key).unwrap_or_default();
+        match edited.set(key, value) {
+            Ok(()) => (),
+            Err(SetError::UnknownKey) => return Err(format!("unknown setting {}", key)),
+            Err(SetError::InvalidValue) => return Err(format!("invalid {} '{}'", key, value)),
+        }
+    }
+    if let Some(binding) = edited.keys.duplicate() {
+        return Err(format!("'{}' is bound to more than one action", binding));
+    }
+    Ok(edited)
+}
+
+/// Lets the player edit and save the settings. Returns a message when they
+/// could not be saved.
+fn run_settings(settings: &mut Settings) -> Option<String> {
+    let mut menu = settings_menu(settings);
+    loop {
+        let result = menu.run()?;
+        match edited_settings(settings, &result) {
+            Ok(edited) => {
+                *settings = edited;
+                let path = settings::default_path()?;
+                return settings::save(settings, &path)
+                    .err()
+                    .map(|e| format!("could not save {}: {}", path.display(), e));
+            }
+            Err(message) => menu.set_message(&message),
+        }
+    }
+}
@@ -0,0 +1,881 @@
+use crate::mineboard::{Board, BoardError, ItemType};
+use crate::utils::Coordinates;
+use std::error::Error;
+use std::fmt;
+
+/// Most cells [`Solver::probabilities`] tries a landmine or no landmine in
+/// before it gives up, which keeps a wide open board from freezing the game.
+const MAX_LAYOUT_STEPS: usize = 2_000_000;
+
+/// Why the chances of landmines could not be worked out.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbabilityError {
+    /// No layout fits the numbers, such as when a flag is wrong.
+    NoLayout,
+    /// There are too many layouts to count them in time.
+    TooManyLayouts,
+    Board(BoardError),
+}
+
+impl Error for ProbabilityError {}
+
+impl fmt::Display for ProbabilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbabilityError::NoLayout => write!(f, "No layout fits the flags and numbers"),
+            ProbabilityError::TooManyLayouts => {
+                write!(f, "Too many possible layouts to work out the chances")
+            }
+            ProbabilityError::Board(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<BoardError> for ProbabilityError {
+    fn from(e: BoardError) -> Self {
+        ProbabilityError::Board(e)
+    }
+}
+
+/// A cell as the player sees it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellView {
+    Closed,
+    Flagged,
+    Opened(i32),
+}
+
+/// Cells proven to be safe or to hold a landmine.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Deduction {
+    pub safe: Vec<Coordinates>,
+    pub landmines: Vec<Coordinates>,
+}
+
+impl Deduction {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.landmines.is_empty()
+    }
+}
+
+/// What a [`Hint`] says about its cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HintKind {
+    Safe,
+    Landmine,
+    /// Nothing is certain. The cell is the least likely to hold a landmine,
+    /// with this chance of it.
+    Guess(f64),
+}
+
+/// One cell to play next, with the opened numbers that decide it. A safe
+/// cell without numbers follows from the number of landmines left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hint {
+    pub pos: Coordinates,
+    pub kind: HintKind,
+    pub reasons: Vec<Coordinates>,
+}
+
+/// "`landmines` of these closed `cells` hold a landmine", as shown by the
+/// number at `source`.
+#[derive(Debug, Clone, PartialEq)]
+struct Constraint {
+    source: usize,
+    cells: Vec<usize>,
+    landmines: usize,
+}
+
+/// Constraints that share cells, so their cells must be counted together.
+#[derive(Debug, Clone, Default)]
+struct Group {
+    cells: Vec<usize>,
+    constraints: Vec<Constraint>,
+}
+
+/// Layouts of a [`Group`] by how many landmines they use: `layouts[k]`
+/// layouts hold `k` landmines, `landmines[k][i]` of which have one on the
+/// group's `i`th cell. Both are scaled by the same factor.
+#[derive(Debug, Clone)]
+struct GroupLayouts {
+    layouts: Vec<f64>,
+    landmines: Vec<Vec<f64>>,
+}
+
+/// Deduces safe cells and landmines from the numbers visible on a board.
+/// Flags are trusted to be on landmines.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    width: usize,
+    height: usize,
+    cells: Vec<CellView>,
+}
+
+impl Solver {
+    pub fn new(board: &Board) -> Result<Self, BoardError> {
+        let config = board.get_config();
+        let mut cells = Vec::with_capacity(config.width * config.height);
+        for y in 0..config.height {
+            for x in 0..config.width {
+                let pos = Coordinates::new(x, y);
+                let view = if board.have_flag(&pos)? {
+                    CellView::Flagged
+                } else if board.is_opened(&pos)? {
+                    match board.get(&pos)? {
+                        ItemType::Number(n) => CellView::Opened(n),
+                        ItemType::Nothing => CellView::Opened(0),
+                        ItemType::Landmine => CellView::Flagged,
+                    }
+                } else {
+                    CellView::Closed
+                };
+                cells.push(view);
+            }
+        }
+        Ok(Self::from_cells(config.width, config.height, cells))
+    }
+
+    /// Builds a solver from `cells` given row by row.
+    pub fn from_cells(width: usize, height: usize, cells: Vec<CellView>) -> Self {
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn get(&self, pos: &Coordinates) -> CellView {
+        self.cells[self.index(pos)]
+    }
+
+    fn index(&self, pos: &Coordinates) -> usize {
+        pos.y * self.width + pos.x
+    }
+
+    fn coordinates(&self, index: usize) -> Coordinates {
+        Coordinates::new(index % self.width, index / self.width)
+    }
+
+    fn neighbours(&self, index: usize) -> Vec<usize> {
+        self.coordinates(index)
+            .neighbours(self.width, self.height)
+            .iter()
+            .map(|pos| self.index(pos))
+            .collect()
+    }
+
+    /// One constraint per opened number that still touches unknown cells.
+    /// `known` holds `Some(true)` for landmines and `Some(false)` for safe
+    /// cells.
+    fn constraints(&self, known: &[Option<bool>]) -> Vec<Constraint> {
+        let mut constraints: Vec<Constraint> = vec![];
+        for (index, cell) in self.cells.iter().enumerate() {
+            let number = match cell {
+                CellView::Opened(n) => *n as usize,
+                _ => continue,
+            };
+            let mut cells = vec![];
+            let mut landmines = 0;
+            for neighbour in self.neighbours(index) {
+                match known[neighbour] {
+                    Some(true) => landmines += 1,
+                    Some(false) => (),
+                    None => cells.push(neighbour),
+                }
+            }
+            if cells.is_empty() || landmines > number {
+                continue;
+            }
+            let constraint = Constraint {
+                source: index,
+                cells,
+                landmines: number - landmines,
+            };
+            if !constraints
+                .iter()
+                .any(|c| c.cells == constraint.cells && c.landmines == constraint.landmines)
+            {
+                constraints.push(constraint);
+            }
+        }
+        constraints
+    }
+
+    fn known(&self) -> Vec<Option<bool>> {
+        self.cells
+            .iter()
+            .map(|cell| match cell {
+                CellView::Closed => None,
+                CellView::Flagged => Some(true),
+                CellView::Opened(_) => Some(false),
+            })
+            .collect()
+    }
+
+    /// Finds every closed cell whose content follows from single numbers
+    /// or from pairs of overlapping numbers, repeating until nothing new
+    /// is learned.
+    pub fn deduce(&self) -> Deduction {
+        let mut known = self.known();
+        let mut deduction = Deduction::default();
+
+        loop {
+            let constraints = self.constraints(&known);
+            let mut found = vec![];
+            for constraint in &constraints {
+                if constraint.landmines == 0 {
+                    found.extend(constraint.cells.iter().map(|&c| (c, false)));
+                } else if constraint.landmines == constraint.cells.len() {
+                    found.extend(constraint.cells.iter().map(|&c| (c, true)));
+                }
+            }
+            if found.is_empty() {
+                for a in &constraints {
+                    for b in &constraints {
+                        if a != b {
+                            found.extend(Self::pair_deduction(a, b));
+                        }
+                    }
+                }
+            }
+
+            let mut progress = false;
+            for (index, landmine) in found {
+                if known[index].is_some() {
+                    continue;
+                }
+                known[index] = Some(landmine);
+                progress = true;
+                let pos = self.coordinates(index);
+                if landmine {
+                    deduction.landmines.push(pos);
+                } else {
+                    deduction.safe.push(pos);
+                }
+            }
+            if !progress {
+                return deduction;
+            }
+        }
+    }
+
+    /// A move that follows from the visible numbers alone, preferring safe
+    /// cells, or the safest guess when there is none. `None` when there is
+    /// no closed cell or no layout fits the flags. `landmines` is the
+    /// number of landmines on the whole board.
+    pub fn hint(&self, landmines: usize) -> Option<Hint> {
+        let known = self.known();
+        let constraints = self.constraints(&known);
+
+        let mut found: Vec<(usize, bool, Vec<usize>)> = vec![];
+        for constraint in &constraints {
+            let sources = vec![constraint.source];
+            if constraint.landmines == 0 {
+                found.push((constraint.cells[0], false, sources));
+            } else if constraint.landmines == constraint.cells.len() {
+                found.push((constraint.cells[0], true, sources));
+            }
+        }
+        if found.is_empty() {
+            for a in &constraints {
+                for b in &constraints {
+                    if let Some(&(index, landmine)) = Self::pair_deduction(a, b).first() {
+                        found.push((index, landmine, vec![b.source, a.source]));
+                    }
+                }
+            }
+        }
+        let best = found
+            .iter()
+            .find(|(_, landmine, _)| !landmine)
+            .or(found.first());
+        if let Some((index, landmine, sources)) = best {
+            return Some(Hint {
+                pos: self.coordinates(*index),
+                kind: if *landmine {
+                    HintKind::Landmine
+                } else {
+                    HintKind::Safe
+                },
+                reasons: sources.iter().map(|&s| self.coordinates(s)).collect(),
+            });
+        }
+        self.safest_guess(&known, &constraints, landmines)
+    }
+
+    /// The closed cell least likely to hold a landmine, by
+    /// [`Solver::probabilities`].
+    fn safest_guess(
+        &self,
+        known: &[Option<bool>],
+        constraints: &[Constraint],
+        landmines: usize,
+    ) -> Option<Hint> {
+        let chances = self.probabilities(landmines).ok()?;
+        let (index, risk) = known
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.is_none())
+            .map(|(index, _)| {
+                let pos = self.coordinates(index);
+                (index, chances[pos.y][pos.x])
+            })
+            .reduce(|best, next| if next.1 < best.1 { next } else { best })?;
+        // The landmine count alone can still make a cell certain.
+        let kind = match risk {
+            risk if risk <= 0.0 => HintKind::Safe,
+            risk if risk >= 1.0 => HintKind::Landmine,
+            risk => HintKind::Guess(risk),
+        };
+        Some(Hint {
+            pos: self.coordinates(index),
+            kind,
+            reasons: constraints
+                .iter()
+                .filter(|c| c.cells.contains(&index))
+                .map(|c| self.coordinates(c.source))
+                .collect(),
+        })
+    }
+
+    /// The chance of a landmine in every cell, row by row, given the
+    /// visible numbers, the flags and that the board holds `landmines` in
+    /// total. Opened cells are 0 and flagged cells 1.
+    ///
+    /// Closed cells next to a number are split into groups that share no
+    /// number, and the layouts of each group are counted on their own. The
+    /// groups are then combined with the closed cells away from the numbers,
+    /// which take whatever landmines are left over.
+    pub fn probabilities(&self, landmines: usize) -> Result<Vec<Vec<f64>>, ProbabilityError> {
+        let known = self.known();
+        if !self.is_consistent(&known) {
+            return Err(ProbabilityError::NoLayout);
+        }
+        let flagged = known.iter().filter(|cell| **cell == Some(true)).count();
+        let left = landmines
+            .checked_sub(flagged)
+            .ok_or(ProbabilityError::NoLayout)?;
+
+        let groups = self.groups(&known);
+        let mut steps = MAX_LAYOUT_STEPS;
+        let counted: Vec<GroupLayouts> = groups
+            .iter()
+            .map(|group| Self::count_layouts(group, left, &mut steps))
+            .collect::<Result<_, _>>()?;
+        let interior = known.iter().filter(|cell| cell.is_none()).count()
+            - groups.iter().map(|group| group.cells.len()).sum::<usize>();
+
+        // weights[s]: ways to put the other `left - s` landmines away from
+        // the numbers, scaled so the largest is 1.
+        let ln_factorial = ln_factorials(interior.max(left));
+        let ln_choose = |n: usize, k: usize| {
+            (k <= n).then(|| ln_factorial[n] - ln_factorial[k] - ln_factorial[n - k])
+        };
+        let ln_weights: Vec<Option<f64>> =
+            (0..=left).map(|s| ln_choose(interior, left - s)).collect();
+        let max_ln = ln_weights
+            .iter()
+            .flatten()
+            .copied()
+            .reduce(f64::max)
+            .ok_or(ProbabilityError::NoLayout)?;
+        let weights: Vec<f64> = ln_weights
+            .iter()
+            .map(|w| w.map_or(0.0, |w| (w - max_ln).exp()))
+            .collect();
+        let weight = |s: usize| weights.get(s).copied().unwrap_or(0.0);
+
+        let all = counted.iter().fold(vec![1.0], |product, group| {
+            convolve(&product, &group.layouts)
+        });
+        let total: f64 = all.iter().enumerate().map(|(s, n)| n * weight(s)).sum();
+        if total <= 0.0 {
+            return Err(ProbabilityError::NoLayout);
+        }
+
+        let mut chances: Vec<f64> = known
+            .iter()
+            .map(|cell| if *cell == Some(true) { 1.0 } else { 0.0 })
+            .collect();
+        if interior > 0 {
+            let expected: f64 = all
+                .iter()
+                .enumerate()
+                .map(|(s, n)| n * weight(s) * left.saturating_sub(s) as f64)
+                .sum();
+            let chance = expected / total / interior as f64;
+            for (index, cell) in known.iter().enumerate() {
+                if cell.is_none() {
+                    chances[index] = chance;
+                }
+            }
+        }
+        for (i, (group, layouts)) in groups.iter().zip(&counted).enumerate() {
+            let others = counted
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .fold(vec![1.0], |product, (_, other)| {
+                    convolve(&product, &other.layouts)
+                });
+            for (c, &cell) in group.cells.iter().enumerate() {
+                let mut sum = 0.0;
+                for (k, landmines) in layouts.landmines.iter().enumerate() {
+                    for (s, n) in others.iter().enumerate() {
+                        sum += landmines[c] * n * weight(k + s);
+                    }
+                }
+                chances[cell] = sum / total;
+            }
+        }
+
+        Ok(chances.chunks(self.width).map(|row| row.to_vec()).collect())
+    }
+
+    /// True when no number has more flags around it than it shows, or too
+    /// few closed cells left to reach it.
+    fn is_consistent(&self, known: &[Option<bool>]) -> bool {
+        self.cells.iter().enumerate().all(|(index, cell)| {
+            let CellView::Opened(number) = cell else {
+                return true;
+            };
+            let neighbours = self.neighbours(index);
+            let flagged = neighbours
+                .iter()
+                .filter(|&&n| known[n] == Some(true))
+                .count();
+            let closed = neighbours.iter().filter(|&&n| known[n].is_none()).count();
+            flagged <= *number as usize && flagged + closed >= *number as usize
+        })
+    }
+
+    /// Splits the constraints into groups that share no cell. The cells of
+    /// each group are ordered so that neighbouring cells come close
+    /// together, which lets [`Solver::count_layouts`] reject bad layouts
+    /// early.
+    fn groups(&self, known: &[Option<bool>]) -> Vec<Group> {
+        let constraints = self.constraints(known);
+        let mut group_of = vec![None; constraints.len()];
+        let mut groups = vec![];
+        for start in 0..constraints.len() {
+            if group_of[start].is_some() {
+                continue;
+            }
+            let mut group = Group::default();
+            let mut queue = std::collections::VecDeque::from([start]);
+            group_of[start] = Some(groups.len());
+            while let Some(current) = queue.pop_front() {
+                let constraint = &constraints[current];
+                for cell in &constraint.cells {
+                    if !group.cells.contains(cell) {
+                        group.cells.push(*cell);
+                    }
+                }
+                for (other, c) in constraints.iter().enumerate() {
+                    if group_of[other].is_none()
+                        && c.cells.iter().any(|cell| constraint.cells.contains(cell))
+                    {
+                        group_of[other] = Some(groups.len());
+                        queue.push_back(other);
+                    }
+                }
+                group.constraints.push(constraint.clone());
+            }
+            groups.push(group);
+        }
+        groups
+    }
+
+    /// Counts the layouts of `group` that satisfy all of its numbers and use
+    /// at most `max_landmines`, taking at most `steps` steps off the search.
+    fn count_layouts(
+        group: &Group,
+        max_landmines: usize,
+        steps: &mut usize,
+    ) -> Result<GroupLayouts, ProbabilityError> {
+        let size = group.cells.len();
+        // For every cell, the constraints it takes part in.
+        let mut touching = vec![vec![]; size];
+        for (c, constraint) in group.constraints.iter().enumerate() {
+            for cell in &constraint.cells {
+                let i = group.cells.iter().position(|other| other == cell).unwrap();
+                touching[i].push(c);
+            }
+        }
+
+        struct Search<'a> {
+            constraints: &'a [Constraint],
+            touching: &'a [Vec<usize>],
+            /// Landmines placed and cells still open for every constraint.
+            placed: Vec<usize>,
+            open: Vec<usize>,
+            layout: Vec<bool>,
+            max_landmines: usize,
+            /// Steps left before the search gives up.
+            steps: usize,
+            layouts: Vec<f64>,
+            landmines: Vec<Vec<f64>>,
+        }
+
+        impl Search<'_> {
+            fn fits(&self, cell: usize) -> bool {
+                self.touching[cell].iter().all(|&c| {
+                    let need = self.constraints[c].landmines;
+                    self.placed[c] <= need && self.placed[c] + self.open[c] >= need
+                })
+            }
+
+            fn place(&mut self, cell: usize, landmine: bool, used: usize) {
+                if self.steps == 0 {
+                    return;
+                }
+                self.steps -= 1;
+                for &c in &self.touching[cell] {
+                    self.open[c] -= 1;
+                    self.placed[c] += landmine as usize;
+                }
+                self.layout[cell] = landmine;
+                if self.fits(cell) {
+                    self.search(cell + 1, used + landmine as usize);
+                }
+                for &c in &self.touching[cell] {
+                    self.open[c] += 1;
+                    self.placed[c] -= landmine as usize;
+                }
+            }
+
+            fn search(&mut self, cell: usize, used: usize) {
+                if cell == self.layout.len() {
+                    self.layouts[used] += 1.0;
+                    for (i, landmine) in self.layout.iter().enumerate() {
+                        if *landmine {
+                            self.landmines[used][i] += 1.0;
+                        }
+                    }
+                    return;
+                }
+                self.place(cell, false, used);
+                if used < self.max_landmines {
+                    self.place(cell, true, used);
+                }
+            }
+        }
+
+        let max_landmines = max_landmines.min(size);
+        let mut search = Search {
+            constraints: &group.constraints,
+            touching: &touching,
+            placed: vec![0; group.constraints.len()],
+            open: group.constraints.iter().map(|c| c.cells.len()).collect(),
+            layout: vec![false; size],
+            max_landmines,
+            steps: *steps,
+            layouts: vec![0.0; max_landmines + 1],
+            landmines: vec![vec![0.0; size]; max_landmines + 1],
+        };
+        search.search(0, 0);
+        if search.steps == 0 {
+            return Err(ProbabilityError::TooManyLayouts);
+        }
+        *steps = search.steps;
+
+        let scale = search.layouts.iter().copied().fold(0.0, f64::max);
+        if scale == 0.0 {
+            return Err(ProbabilityError::NoLayout);
+        }
+        Ok(GroupLayouts {
+            layouts: search.layouts.iter().map(|n| n / scale).collect(),
+            landmines: search
+                .landmines
+                .iter()
+                .map(|row| row.iter().map(|n| n / scale).collect())
+                .collect(),
+        })
+    }
+
+    /// What `a` tells about the cells of `b` that `a` does not cover.
+    fn pair_deduction(a: &Constraint, b: &Constraint) -> Vec<(usize, bool)> {
+        let shared = b.cells.iter().filter(|c| a.cells.contains(c)).count();
+        if shared == 0 {
+            return vec![];
+        }
+        let only_a = a.cells.len() - shared;
+        let only_b: Vec<usize> = b
+            .cells
+            .iter()
+            .copied()
+            .filter(|c| !a.cells.contains(c))
+            .collect();
+        if only_b.is_empty() {
+            return vec![];
+        }
+
+        let max_shared = shared.min(a.landmines).min(b.landmines);
+        let min_shared = a.landmines.saturating_sub(only_a);
+        if b.landmines - max_shared == only_b.len() {
+            only_b.into_iter().map(|c| (c, true)).collect()
+        } else if b.landmines <= min_shared {
+            only_b.into_iter().map(|c| (c, false)).collect()
+        } else {
+            vec![]
+        }
+    }
+}
+
+/// ln(n!) for every n up to `max`.
+fn ln_factorials(max: usize) -> Vec<f64> {
+    let mut table = Vec::with_capacity(max + 1);
+    table.push(0.0);
+    for n in 1..=max {
+        table.push(table[n - 1] + (n as f64).ln());
+    }
+    table
+}
+
+/// Product of two polynomials given by their coefficients.
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut product = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    product
+}
+
+/// Plays `board` from `start` using only deductions and reports whether it
+/// can be cleared without guessing. The board is left in its final state.
+pub fn solvable_without_guessing(
+    board: &mut Board,
+    start: &Coordinates,
+) -> Result<bool, BoardError> {
+    if board.click(start)?.hit_landmine() {
+        return Ok(false);
+    }
+    while !board.is_cleared() {
+        let deduction = Solver::new(board)?.deduce();
+        if deduction.safe.is_empty() {
+            return Ok(false);
+        }
+        for pos in &deduction.landmines {
+            board.add_flag(pos)?;
+        }
+        for pos in &deduction.safe {
+            board.click(pos)?;
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::test::test_board;
+
+    #[test]
+    fn single_number_deductions() {
+        let cells = vec![
+            CellView::Opened(0),
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Opened(1),
+        ];
+        let deduction = Solver::from_cells(4, 1, cells).deduce();
+        assert_eq!(deduction.safe, vec![Coordinates::new(1, 0)]);
+        assert_eq!(deduction.landmines, vec![Coordinates::new(2, 0)]);
+    }
+
+    #[test]
+    fn one_two_one_pattern() {
+        // Closed row above an opened 1-2-1 row.
+        let cells = vec![
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Opened(1),
+            CellView::Opened(2),
+            CellView::Opened(1),
+        ];
+        let deduction = Solver::from_cells(3, 2, cells).deduce();
+        assert_eq!(
+            deduction.landmines,
+            vec![Coordinates::new(2, 0), Coordinates::new(0, 0)]
+        );
+        assert_eq!(deduction.safe, vec![Coordinates::new(1, 0)]);
+    }
+
+    #[test]
+    fn fifty_fifty_has_no_deduction() {
+        let cells = vec![
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Opened(1),
+            CellView::Opened(1),
+        ];
+        let deduction = Solver::from_cells(2, 2, cells).deduce();
+        assert!(deduction.is_empty());
+    }
+
+    #[test]
+    fn hints_name_the_numbers() {
+        let cells = vec![
+            CellView::Opened(0),
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Opened(1),
+        ];
+        let hint = Solver::from_cells(4, 1, cells).hint(1).unwrap();
+        assert_eq!(hint.pos, Coordinates::new(1, 0));
+        assert_eq!(hint.kind, HintKind::Safe);
+        assert_eq!(hint.reasons, vec![Coordinates::new(0, 0)]);
+
+        let cells = vec![
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Opened(1),
+            CellView::Opened(2),
+            CellView::Opened(1),
+        ];
+        let hint = Solver::from_cells(3, 2, cells).hint(2).unwrap();
+        assert_eq!(hint.kind, HintKind::Landmine);
+        assert_eq!(hint.reasons.len(), 2);
+    }
+
+    #[test]
+    fn guess_picks_the_lowest_risk() {
+        // The only landmine is next to the 1, so the cells away from it
+        // are safe, though no number touches them.
+        let cells = vec![
+            CellView::Closed,
+            CellView::Opened(1),
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Closed,
+        ];
+        let hint = Solver::from_cells(5, 1, cells).hint(1).unwrap();
+        assert_eq!(hint.pos, Coordinates::new(3, 0));
+        assert_eq!(hint.kind, HintKind::Safe);
+        assert!(hint.reasons.is_empty());
+
+        let cells = vec![
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Opened(1),
+            CellView::Opened(1),
+        ];
+        let hint = Solver::from_cells(2, 2, cells).hint(1).unwrap();
+        assert_eq!(hint.kind, HintKind::Guess(0.5));
+        assert_eq!(hint.reasons, vec![Coordinates::new(0, 1)]);
+    }
+
+    #[test]
+    fn probabilities_use_the_landmine_count() {
+        let cells = vec![
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Opened(1),
+            CellView::Opened(1),
+        ];
+        let chances = Solver::from_cells(2, 2, cells).probabilities(1).unwrap();
+        assert_eq!(chances, vec![vec![0.5, 0.5], vec![0.0, 0.0]]);
+
+        let cells = vec![
+            CellView::Closed,
+            CellView::Opened(1),
+            CellView::Closed,
+            CellView::Closed,
+            CellView::Closed,
+        ];
+        let solver = Solver::from_cells(5, 1, cells);
+        assert_eq!(
+            solver.probabilities(1).unwrap(),
+            vec![vec![0.5, 0.0, 0.5, 0.0, 0.0]]
+        );
+        assert_eq!(
+            solver.probabilities(2).unwrap(),
+            vec![vec![0.5, 0.0, 0.5, 0.5, 0.5]]
+        );
+        assert_eq!(solver.probabilities(4), Err(ProbabilityError::NoLayout));
+    }
+
+    #[test]
+    fn too_many_layouts_are_not_counted() {
+        // Every column of the two closed rows can hold its landmine in
+        // either row, which gives 2^60 layouts.
+        let width = 60;
+        let mut cells = vec![CellView::Closed; width];
+        cells.extend(vec![CellView::Opened(3); width]);
+        cells.extend(vec![CellView::Closed; width]);
+        let solver = Solver::from_cells(width, 3, cells);
+        assert_eq!(
+            solver.probabilities(width),
+            Err(ProbabilityError::TooManyLayouts)
+        );
+    }
+
+    #[test]
+    fn probabilities_match_every_layout() {
+        let landmines = [
+            Coordinates::new(0, 0),
+            Coordinates::new(4, 0),
+            Coordinates::new(2, 3),
+            Coordinates::new(5, 3),
+            Coordinates::new(0, 4),
+        ];
+        let mut board = test_board(6, 5, &landmines);
+        board.click(&Coordinates::new(2, 1)).unwrap();
+        board.click(&Coordinates::new(5, 0)).unwrap();
+        board.click(&Coordinates::new(1, 4)).unwrap();
+        board.add_flag(&Coordinates::new(4, 0)).unwrap();
+        let solver = Solver::new(&board).unwrap();
+        let chances = solver.probabilities(landmines.len()).unwrap();
+
+        // Try every layout of the closed cells.
+        let known = solver.known();
+        let closed: Vec<usize> = (0..known.len()).filter(|&i| known[i].is_none()).collect();
+        assert!(closed.len() < 20);
+        let mut fitting = 0.0;
+        let mut counts = vec![0.0; known.len()];
+        for layout in 0..1u32 << closed.len() {
+            let mut cells = known.clone();
+            for (bit, &cell) in closed.iter().enumerate() {
+                cells[cell] = Some(layout & (1 << bit) != 0);
+            }
+            let placed = cells.iter().filter(|cell| **cell == Some(true)).count();
+            let fits = placed == landmines.len()
+                && solver.cells.iter().enumerate().all(|(i, cell)| match cell {
+                    CellView::Opened(n) => {
+                        let around = solver.neighbours(i);
+                        around.iter().filter(|&&c| cells[c] == Some(true)).count() == *n as usize
+                    }
+                    _ => true,
+                });
+            if fits {
+                fitting += 1.0;
+                for &cell in &closed {
+                    if cells[cell] == Some(true) {
+                        counts[cell] += 1.0;
+                    }
+                }
+            }
+        }
+        for &cell in &closed {
+            let pos = solver.coordinates(cell);
+            let expected = counts[cell] / fitting;
+            assert!((chances[pos.y][pos.x] - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn solvable_board() {
+        let mut board = test_board(4, 4, &[Coordinates::new(3, 3)]);
+        assert!(solvable_without_guessing(&mut board, &Coordinates::new(0, 0)).unwrap());
+
+        let mut board = test_board(2, 1, &[Coordinates::new(1, 0)]);
+        assert!(solvable_without_guessing(&mut board, &Coordinates::new(0, 0)).unwrap());
+
+        let mut board = test_board(3, 1, &[Coordinates::new(0, 0)]);
+        assert!(!solvable_without_guessing(&mut board, &Coordinates::new(1, 0)).unwrap());
+    }
+}
@@ -0,0 +1,265 @@
+use crate::engine::{Engine, GameState};
+use crate::scores::BoardKey;
+use crate::utils::{write_atomically, FileLock};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const HEADER: &str = "terminal_minesweeper stats";
+
+/// Version written by [`StatsTable::to_string`].
+pub const STATS_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum StatsError {
+    Io(std::io::Error),
+    NotAStatsTable,
+    UnsupportedVersion(u32),
+    InvalidLine(usize),
+}
+
+impl Error for StatsError {}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatsError::Io(e) => write!(f, "{}", e),
+            StatsError::NotAStatsTable => write!(f, "not a statistics file"),
+            StatsError::UnsupportedVersion(version) => write!(
+                f,
+                "statistics version {} is newer than the supported version {}",
+                version, STATS_VERSION
+            ),
+            StatsError::InvalidLine(line) => write!(f, "statistics line {} is invalid", line),
+        }
+    }
+}
+
+impl From<std::io::Error> for StatsError {
+    fn from(e: std::io::Error) -> Self {
+        StatsError::Io(e)
+    }
+}
+
+/// Totals over every finished game on one board.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub played: u64,
+    pub won: u64,
+    pub lost: u64,
+    /// Wins in a row, up to the last game.
+    pub current_streak: u64,
+    pub longest_streak: u64,
+    /// Time spent on won games, for the average.
+    pub win_time: Duration,
+    pub cells_revealed: u64,
+}
+
+impl Stats {
+    /// Counts a finished game. Games that were quit are not counted, since
+    /// they are saved to be resumed.
+    pub fn record(&mut self, state: &GameState, time: Duration, cells_revealed: usize) {
+        match state {
+            GameState::Win => {
+                self.won += 1;
+                self.current_streak += 1;
+                self.longest_streak = self.longest_streak.max(self.current_streak);
+                self.win_time += time;
+            }
+            GameState::HitMine => {
+                self.lost += 1;
+                self.current_streak = 0;
+            }
+            GameState::Playing | GameState::Quit => return,
+        }
+        self.played += 1;
+        self.cells_revealed += cells_revealed as u64;
+    }
+
+    /// Share of games won, from 0 to 1.
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.played > 0).then(|| self.won as f64 / self.played as f64)
+    }
+
+    /// Average time of a won game.
+    pub fn average_time(&self) -> Option<Duration> {
+        (self.won > 0).then(|| self.win_time / self.won as u32)
+    }
+}
+
+/// Statistics for every board that has been played.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StatsTable {
+    tables: BTreeMap<BoardKey, Stats>,
+}
+
+impl StatsTable {
+    pub fn get(&self, key: &BoardKey) -> Stats {
+        self.tables.get(key).cloned().unwrap_or_default()
+    }
+
+    pub fn get_mut(&mut self, key: BoardKey) -> &mut Stats {
+        self.tables.entry(key).or_default()
+    }
+
+    /// Forgets the statistics of `key`.
+    pub fn reset(&mut self, key: &BoardKey) {
+        self.tables.remove(key);
+    }
+
+    pub fn parse(text: &str) -> Result<Self, StatsError> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line) != Some(HEADER) {
+            return Err(StatsError::NotAStatsTable);
+        }
+        let version = match lines.next() {
+            Some((_, line)) => line
+                .strip_prefix("version=")
+                .and_then(|version| version.parse().ok())
+                .ok_or(StatsError::NotAStatsTable)?,
+            None => return Err(StatsError::NotAStatsTable),
+        };
+        if version > STATS_VERSION {
+            return Err(StatsError::UnsupportedVersion(version));
+        }
+
+        let mut table = StatsTable::default();
+        for (i, line) in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let (key, stats) = parse_stats(line).ok_or(StatsError::InvalidLine(i + 1))?;
+            table.tables.insert(key, stats);
+        }
+        Ok(table)
+    }
+}
+
+fn parse_stats(line: &str) -> Option<(BoardKey, Stats)> {
+    let mut fields = line.split('\t');
+    let key = fields.next()?.parse().ok()?;
+    let mut next = || -> Option<u64> { fields.next()?.parse().ok() };
+    let stats = Stats {
+        played: next()?,
+        won: next()?,
+        lost: next()?,
+        current_streak: next()?,
+        longest_streak: next()?,
+        win_time: Duration::from_millis(next()?),
+        cells_revealed: next()?,
+    };
+    Some((key, stats))
+}
+
+impl fmt::Display for StatsTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}\nversion={}", HEADER, STATS_VERSION)?;
+        for (key, stats) in &self.tables {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                key,
+                stats.played,
+                stats.won,
+                stats.lost,
+                stats.current_streak,
+                stats.longest_streak,
+                stats.win_time.as_millis(),
+                stats.cells_revealed
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the statistics are kept.
+pub fn default_path() -> Option<PathBuf> {
+    Some(crate::utils::data_dir()?.join("stats.txt"))
+}
+
+/// Reads the statistics at `path`, starting empty when there is no file
+/// yet.
+pub fn load(path: &Path) -> Result<StatsTable, StatsError> {
+    match fs::read_to_string(path) {
+        Ok(text) => StatsTable::parse(&text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(StatsTable::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Changes the statistics at `path` with `update` while holding its lock.
+pub fn update<T>(path: &Path, update: impl FnOnce(&mut StatsTable) -> T) -> Result<T, StatsError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let _lock = FileLock::acquire(path)?;
+    let mut table = load(path)?;
+    let result = update(&mut table);
+    write_atomically(path, &table.to_string())?;
+    Ok(result)
+}
+
+/// Counts the finished game of `engine`.
+pub fn record(path: &Path, engine: &Engine) -> Result<(), StatsError> {
+    let cells = *engine.board().opened_count();
+    update(path, |table| {
+        table
+            .get_mut(BoardKey::from(engine))
+            .record(engine.state(), engine.elapsed(), cells)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KEY: BoardKey = BoardKey {
+        width: 9,
+        height: 9,
+        landmines: 10,
+    };
+
+    #[test]
+    fn streaks_and_averages() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.win_rate(), None);
+        assert_eq!(stats.average_time(), None);
+
+        let second = Duration::from_secs(1);
+        stats.record(&GameState::Win, 10 * second, 71);
+        stats.record(&GameState::Win, 20 * second, 71);
+        stats.record(&GameState::HitMine, 5 * second, 12);
+        stats.record(&GameState::Win, 30 * second, 71);
+        stats.record(&GameState::Quit, 5 * second, 3);
+
+        assert_eq!((stats.played, stats.won, stats.lost), (4, 3, 1));
+        assert_eq!((stats.current_streak, stats.longest_streak), (1, 2));
+        assert_eq!(stats.win_rate(), Some(0.75));
+        assert_eq!(stats.average_time(), Some(20 * second));
+        assert_eq!(stats.cells_revealed, 225);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut table = StatsTable::default();
+        table
+            .get_mut(KEY)
+            .record(&GameState::Win, Duration::from_millis(12_345), 71);
+        table
+            .get_mut(KEY)
+            .record(&GameState::HitMine, Duration::ZERO, 3);
+
+        let parsed = StatsTable::parse(&table.to_string()).unwrap();
+        assert_eq!(parsed, table);
+        assert!(matches!(
+            StatsTable::parse(&format!("{}\nversion=1\n9x9:10\t1\n", HEADER)),
+            Err(StatsError::InvalidLine(3))
+        ));
+
+        table.reset(&KEY);
+        assert_eq!(table.get(&KEY), Stats::default());
+    }
+}
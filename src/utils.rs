@@ -1,3 +1,13 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub struct Coordinates {
     pub x: usize,
@@ -6,6 +16,227 @@ pub struct Coordinates {
 
 impl Coordinates {
     pub fn new(x: usize, y: usize) -> Coordinates {
-        Coordinates {x, y}
+        Coordinates { x, y }
+    }
+
+    /// Returns the up to 8 cells surrounding this one that lie inside a
+    /// `width` x `height` board.
+    pub fn neighbours(&self, width: usize, height: usize) -> Vec<Coordinates> {
+        let mut neighbours = Vec::with_capacity(8);
+        for y in self.y.saturating_sub(1)..=self.y + 1 {
+            for x in self.x.saturating_sub(1)..=self.x + 1 {
+                if x >= width || y >= height || (x == self.x && y == self.y) {
+                    continue;
+                }
+                neighbours.push(Coordinates { x, y });
+            }
+        }
+        neighbours
+    }
+}
+
+/// Seed for the landmine layout, written in base 36 so it is easy to share.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Seed(pub u64);
+
+impl Seed {
+    pub fn random() -> Seed {
+        Seed(rand::random())
+    }
+
+    /// The generator that lays out the landmines of this seed. ChaCha
+    /// gives the same numbers on every platform and version.
+    pub fn rng(&self) -> ChaCha8Rng {
+        let mut bytes = [0; 32];
+        bytes[..8].copy_from_slice(&self.0.to_le_bytes());
+        ChaCha8Rng::from_seed(bytes)
+    }
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut n = self.0;
+        let mut digits = vec![];
+        loop {
+            digits.push(std::char::from_digit((n % 36) as u32, 36).unwrap());
+            n /= 36;
+            if n == 0 {
+                break;
+            }
+        }
+        let text: String = digits.iter().rev().collect();
+        write!(f, "{}", text.to_uppercase())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseSeedError;
+
+impl std::error::Error for ParseSeedError {}
+
+impl fmt::Display for ParseSeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid seed")
+    }
+}
+
+impl FromStr for Seed {
+    type Err = ParseSeedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s.trim(), 36)
+            .map(Seed)
+            .map_err(|_| ParseSeedError)
     }
-}
\ No newline at end of file
+}
+
+/// A value that does not name any of the allowed options.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptionError(pub String);
+
+impl std::error::Error for ParseOptionError {}
+
+impl fmt::Display for ParseOptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown option '{}'", self.0)
+    }
+}
+
+/// Formats seconds since the Unix epoch as a UTC `YYYY-MM-DD` date.
+pub fn format_date(unix_seconds: u64) -> String {
+    // Howard Hinnant's civil_from_days, on days counted from 0000-03-01.
+    let days = (unix_seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Directory for saved games and records, following the XDG base
+/// directory spec.
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Directory for the settings file, following the XDG base directory
+/// spec.
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+fn xdg_dir(variable: &str, fallback: &str) -> Option<PathBuf> {
+    let base = match std::env::var_os(variable) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(fallback),
+    };
+    Some(base.join("terminal_minesweeper"))
+}
+
+/// How long [`FileLock::acquire`] waits for another run to finish writing.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+/// A lock older than this was left behind by a run that died.
+const STALE_LOCK: Duration = Duration::from_secs(30);
+
+/// Holds `<path>.lock` while it lives, so that two runs finishing at once
+/// take turns updating the file at `path`.
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Waits for the lock, failing with [`io::ErrorKind::TimedOut`] when
+    /// another run keeps it too long.
+    pub fn acquire(path: &Path) -> io::Result<Self> {
+        let mut lock = path.as_os_str().to_owned();
+        lock.push(".lock");
+        let path = PathBuf::from(lock);
+
+        let start = SystemTime::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    let age = fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok());
+                    if age.is_some_and(|age| age > STALE_LOCK) {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if start.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("{} is held by another run", path.display()),
+                        ));
+                    }
+                    thread::sleep(Duration::from_millis(20));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Writes `contents` to a temporary file and renames it over `path`, so a
+/// reader never sees half of it.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", std::process::id()));
+    let temporary = PathBuf::from(temporary);
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn neighbours_stay_on_board() {
+        let corner = Coordinates::new(0, 0).neighbours(3, 3);
+        assert_eq!(
+            corner,
+            vec![
+                Coordinates::new(1, 0),
+                Coordinates::new(0, 1),
+                Coordinates::new(1, 1)
+            ]
+        );
+        assert_eq!(Coordinates::new(1, 1).neighbours(3, 3).len(), 8);
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_700_000_000), "2023-11-14");
+    }
+
+    #[test]
+    fn seed_round_trip() {
+        for n in [0, 35, 36, u64::MAX] {
+            let seed = Seed(n);
+            assert_eq!(seed.to_string().parse::<Seed>(), Ok(seed));
+        }
+        assert_eq!(Seed(35).to_string(), "Z");
+        assert_eq!("z".parse::<Seed>(), Ok(Seed(35)));
+        assert_eq!("not a seed!".parse::<Seed>(), Err(ParseSeedError));
+    }
+}
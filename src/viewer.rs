@@ -0,0 +1,279 @@
+#![cfg(feature = "bracketed-paste")]
+//! Watches a [`Replay`] in the terminal, drawn by the same [`Board`] as a
+//! live game.
+//!
+//! [`Board`]: crate::mineboard::Board
+
+use crate::engine::GameState;
+use crate::mineboard::BoardError;
+use crate::replay::{self, Playback, Replay, ReplayError};
+use crate::settings::Settings;
+use crossterm::cursor;
+use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::execute;
+use crossterm::style::Print;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
+use std::io::stdout;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How often the clock moves on while playing.
+const TICK: Duration = Duration::from_millis(20);
+
+/// Playback speeds, as multiples of the recorded speed.
+const SPEEDS: [f64; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+const NORMAL_SPEED: usize = 2;
+
+const HELP: &str = "Space: pause  +/-: speed  Left/Right: step  \
+Home/End: start/end  j: jump to time  q: quit";
+
+#[derive(Clone, Debug)]
+pub struct Viewer {
+    playback: Playback,
+    playing: bool,
+    speed: usize,
+    /// Time in the recording, which runs ahead of the last event played
+    /// until the next one is due.
+    clock: Duration,
+    /// Seconds typed after asking to jump.
+    jump: Option<String>,
+    message: Option<String>,
+    quit: bool,
+}
+
+impl Viewer {
+    /// A viewer for `replay`, drawn with the symbols and colours of
+    /// `settings`.
+    pub fn new(mut replay: Replay, settings: &Settings) -> Self {
+        settings.apply(&mut replay.board);
+        replay.board.draw_border();
+        let message = replay
+            .truncated
+            .then(|| "Some cursor moves were left out, the game was too long".to_string());
+        Self {
+            playback: Playback::new(replay),
+            playing: true,
+            speed: NORMAL_SPEED,
+            clock: Duration::ZERO,
+            jump: None,
+            message,
+            quit: false,
+        }
+    }
+
+    pub fn run(&mut self) {
+        enable_raw_mode().unwrap();
+        let mut stdout = stdout();
+        execute!(stdout, cursor::Hide).unwrap();
+        self.rander();
+        let mut last = Instant::now();
+        while !self.quit {
+            let mut changed = false;
+            if poll(TICK).unwrap() {
+                if let Event::Key(key_event) = read().unwrap() {
+                    self.process_key(key_event);
+                    changed = true;
+                }
+            }
+            let now = Instant::now();
+            if self.playing {
+                self.clock += (now - last).mul_f64(SPEEDS[self.speed]);
+                changed |= self.catch_up();
+            }
+            last = now;
+            if changed {
+                self.rander();
+            }
+        }
+        execute!(stdout, Clear(ClearType::All), cursor::Show).unwrap();
+        disable_raw_mode().unwrap();
+    }
+
+    /// Plays every event that is due on the clock. Returns whether any was.
+    fn catch_up(&mut self) -> bool {
+        let mut played = false;
+        while self
+            .playback
+            .next_time()
+            .is_some_and(|time| time <= self.clock)
+        {
+            match self.playback.next_event() {
+                Ok(next) => played |= next,
+                Err(e) => {
+                    self.show_error(e);
+                    return true;
+                }
+            }
+        }
+        if self.playback.is_at_end() {
+            self.playing = false;
+            played = true;
+        }
+        played
+    }
+
+    fn process_key(&mut self, key_event: KeyEvent) {
+        if key_event.modifiers.difference(KeyModifiers::SHIFT) != KeyModifiers::NONE {
+            return;
+        }
+        self.message = None;
+        if let Some(jump) = &mut self.jump {
+            match key_event.code {
+                KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => jump.push(c),
+                KeyCode::Backspace => {
+                    jump.pop();
+                }
+                KeyCode::Enter => self.jump(),
+                KeyCode::Esc => self.jump = None,
+                _ => (),
+            }
+            return;
+        }
+
+        match key_event.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char(' ') => {
+                if self.playback.is_at_end() {
+                    self.seek(0);
+                }
+                self.playing = !self.playing;
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.speed = (self.speed + 1).min(SPEEDS.len() - 1)
+            }
+            KeyCode::Char('-') => self.speed = self.speed.saturating_sub(1),
+            KeyCode::Right => {
+                self.playing = false;
+                if let Err(e) = self.playback.step_forward() {
+                    self.show_error(e);
+                }
+                self.clock = self.playback.time();
+            }
+            KeyCode::Left => {
+                self.playing = false;
+                if let Err(e) = self.playback.step_back() {
+                    self.show_error(e);
+                }
+                self.clock = self.playback.time();
+            }
+            KeyCode::Home => self.seek(0),
+            KeyCode::End => self.seek(usize::MAX),
+            KeyCode::Char('j') => self.jump = Some(String::new()),
+            _ => (),
+        }
+    }
+
+    fn seek(&mut self, position: usize) {
+        if let Err(e) = self.playback.seek(position) {
+            self.show_error(e);
+        }
+        self.clock = self.playback.time();
+    }
+
+    /// Goes to the time typed after asking to jump, as the game clock
+    /// showed it.
+    fn jump(&mut self) {
+        let typed = self.jump.take().unwrap_or_default();
+        let time = typed
+            .parse()
+            .ok()
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok());
+        let Some(time) = time else {
+            self.message = Some(format!("'{}' is not a number of seconds", typed));
+            return;
+        };
+        let time = self.playback.replay().time_at(time);
+        if let Err(e) = self.playback.seek_time(time) {
+            self.show_error(e);
+        }
+        self.clock = time.min(self.playback.duration());
+    }
+
+    /// Stops at an event that cannot be played and says why.
+    fn show_error(&mut self, e: BoardError) {
+        self.playing = false;
+        self.message = Some(format!("could not play the replay: {}", e));
+    }
+
+    fn rander(&mut self) {
+        let mut stdout = stdout();
+        execute!(stdout, Clear(ClearType::All), cursor::MoveTo(0, 0)).unwrap();
+        let status = self.status();
+        let cursor = self.playback.cursor();
+        let board = self.playback.engine_mut().board_mut();
+        board.set_highlight(cursor);
+        board.draw_status(&status);
+        board.print();
+
+        // Times are on the game clock, like the status line.
+        let replay = self.playback.replay();
+        let progress = format!(
+            "{} {}/{}  {:.1}s / {:.1}s  Speed {}x",
+            if self.playing { "Playing" } else { "Paused " },
+            self.playback.position(),
+            replay.events.len(),
+            replay.clock_at(self.clock).as_secs_f64(),
+            replay.clock_at(self.playback.duration()).as_secs_f64(),
+            SPEEDS[self.speed]
+        );
+        let prompt = match (&self.jump, &self.message) {
+            (Some(jump), _) => format!("Jump to second: {}", jump),
+            (None, Some(message)) => message.clone(),
+            (None, None) => String::new(),
+        };
+        for (line, text) in [progress, prompt, HELP.to_string()].iter().enumerate() {
+            execute!(
+                stdout,
+                cursor::MoveTo(0, self.status_row(line as u16)),
+                Print(text)
+            )
+            .unwrap();
+        }
+    }
+
+    fn status(&self) -> String {
+        let engine = self.playback.engine();
+        let state = match engine.state() {
+            GameState::Playing if !engine.is_started() => "Ready",
+            GameState::Playing => "Playing",
+            GameState::Win => "Won",
+            GameState::HitMine => "Lost",
+            GameState::Quit => "Quit",
+        };
+        format!(
+            "💣 {:<4} ⏱ {:<5} {:<8} Seed: {}  REPLAY",
+            engine.remaining_landmines(),
+            self.playback.replay().clock_at(self.clock).as_secs(),
+            state,
+            engine.seed()
+        )
+    }
+
+    /// Terminal row of the `line`th line below the board.
+    fn status_row(&self, line: u16) -> u16 {
+        self.playback.engine().board().get_config().height as u16 + 2 + line
+    }
+}
+
+/// Watches the replay at `path`.
+pub fn watch(path: &Path, settings: &Settings) -> Result<(), ReplayError> {
+    let replay = replay::load(path)?;
+    Viewer::new(replay, settings).run();
+    Ok(())
+}
+
+/// Watches the most recently finished game. Returns a message when there
+/// is none or it cannot be read.
+pub fn watch_last(settings: &Settings) -> Option<String> {
+    let last = replay::default_dir()
+        .map(|dir| replay::list(&dir))
+        .transpose()
+        .map(|replays| replays.and_then(|mut replays| replays.pop()));
+    match last {
+        Ok(Some(path)) => watch(&path, settings)
+            .err()
+            .map(|e| format!("could not watch {}: {}", path.display(), e)),
+        Ok(None) => Some("No finished game has been recorded yet".to_string()),
+        Err(e) => Some(format!("could not list the replays: {}", e)),
+    }
+}